@@ -0,0 +1,466 @@
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anchor_lang::AccountDeserialize;
+use big_list::{
+    instruction::InitializeBatchProcess,
+    state::{BatchAction, BatchProcess, BatchTrigger, IndexType},
+};
+use big_list_client::{
+    derive_batch_process, derive_bucket, derive_root, derive_shard, diff as diff_elements,
+    initialize, initialize_batch_process, initialize_batch_shard, initialize_bucket, normalize,
+    plan_appends, read_csv, read_json, sync_shards, BigListReader, Checkpoint, ListRef, Snapshot,
+};
+use solana_sdk::{
+    instruction::Instruction, pubkey::Pubkey, signature::Signature, signer::Signer,
+    transaction::Transaction,
+};
+
+use crate::{
+    config::{Config, RpcFetcher},
+    ExportFormat,
+};
+
+// Buckets created per transaction, small enough for ids of any length
+const BUCKETS_PER_TRANSACTION: usize = 8;
+
+pub fn create(config: &Config, id: &str, unique: bool, sorted: bool) -> Result<(), String> {
+    let authority = config.keypair.pubkey();
+    let root = derive_root(&authority, id);
+    let index_type = if sorted {
+        IndexType::SortedIndex
+    } else {
+        IndexType::GrowableIndex
+    };
+    // A unique list interrupted while creating its buckets picks up where
+    // it stopped
+    if exists(config, &[root])?[0] {
+        if !unique {
+            return Err(format!("{} already exists", root));
+        }
+        eprintln!("{} already exists, resuming its buckets", root);
+    } else {
+        let signature = send(
+            config,
+            initialize(&authority, &authority, id, 3, unique, index_type),
+        )?;
+        println!("Created {} in {}", root, signature);
+    }
+    if !unique {
+        return Ok(());
+    }
+
+    let buckets: Vec<Pubkey> = (0..=u8::MAX)
+        .map(|prefix| derive_bucket(&root, prefix))
+        .collect();
+    let missing: Vec<u8> = exists(config, &buckets)?
+        .into_iter()
+        .zip(0..=u8::MAX)
+        .filter(|(exists, _)| !exists)
+        .map(|(_, prefix)| prefix)
+        .collect();
+    let mut created = buckets.len() - missing.len();
+    for prefixes in missing.chunks(BUCKETS_PER_TRANSACTION) {
+        let ixs = prefixes
+            .iter()
+            .map(|prefix| initialize_bucket(&authority, id, *prefix))
+            .collect::<Vec<_>>();
+        send_all(config, &ixs)?;
+        created += prefixes.len();
+        eprintln!("Created buckets {}/{}", created, buckets.len());
+    }
+    Ok(())
+}
+
+pub fn append(
+    config: &Config,
+    id: &str,
+    csv: Option<PathBuf>,
+    json: Option<PathBuf>,
+    dedupe: bool,
+) -> Result<(), String> {
+    let authority = config.keypair.pubkey();
+    let (input, values) = match (csv, json) {
+        (Some(path), _) => {
+            let values = read_csv(open(&path)?);
+            (path, values)
+        }
+        (None, Some(path)) => {
+            let values = read_json(open(&path)?);
+            (path, values)
+        }
+        (None, None) => return Err("Pass --csv or --json".to_string()),
+    };
+    let addresses = values
+        .and_then(|values| normalize(&values, dedupe))
+        .map_err(|err| err.to_string())?;
+    let root = BigListReader::new(RpcFetcher(&config.rpc), authority, id)
+        .root()
+        .map_err(|err| err.to_string())?;
+
+    let checkpoint_path = Checkpoint::path(&input);
+    let checkpoint = Checkpoint::load_or_start(
+        &checkpoint_path,
+        &derive_root(&authority, id),
+        root.total_elements,
+        &addresses,
+    )
+    .map_err(|err| err.to_string())?;
+    let uploaded = checkpoint
+        .uploaded(root.total_elements, addresses.len())
+        .map_err(|err| err.to_string())?;
+    if uploaded > 0 {
+        eprintln!(
+            "Resuming after {} of {} addresses",
+            uploaded,
+            addresses.len()
+        );
+    }
+
+    let list = ListRef {
+        authority,
+        id: id.to_string(),
+        total_elements: root.total_elements,
+        unique: root.unique,
+    };
+    let remaining = &addresses[uploaded..];
+    for step in plan_appends(list.total_elements, remaining.len(), list.unique) {
+        let list = ListRef {
+            total_elements: step.start_index,
+            ..list.clone()
+        };
+        send(config, step.instruction(&list, remaining))?;
+        eprintln!(
+            "Appended {}/{}",
+            uploaded + step.offset + step.count,
+            addresses.len()
+        );
+    }
+
+    Checkpoint::finish(&checkpoint_path);
+    println!(
+        "{} now holds {} elements",
+        list.root(),
+        list.total_elements + remaining.len() as u32
+    );
+    Ok(())
+}
+
+pub fn show(config: &Config, id: &str, authority: Option<String>) -> Result<(), String> {
+    let authority = authority_or_keypair(config, authority)?;
+    let root = BigListReader::new(RpcFetcher(&config.rpc), authority, id)
+        .root()
+        .map_err(|err| err.to_string())?;
+
+    println!("List:       {}", derive_root(&authority, id));
+    println!("Authority:  {}", root.authority);
+    println!("Index type: {:?}", root.index_type);
+    println!("Unique:     {}", root.unique);
+    println!("Elements:   {}", root.total_elements);
+    if root.capacity > 0 {
+        println!("Capacity:   {}", root.capacity);
+    }
+    println!("Branches:   {}", root.len);
+    Ok(())
+}
+
+pub fn export(
+    config: &Config,
+    id: &str,
+    format: ExportFormat,
+    authority: Option<String>,
+) -> Result<(), String> {
+    let authority = authority_or_keypair(config, authority)?;
+    let reader = BigListReader::new(RpcFetcher(&config.rpc), authority, id);
+    let elements = reader
+        .elements()
+        .map_err(|err| err.to_string())?
+        .map(|element| element.map(|element| element.to_string()))
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|err| err.to_string())?;
+
+    match format {
+        ExportFormat::Csv => {
+            println!("index,address");
+            for (index, element) in elements.iter().enumerate() {
+                println!("{},{}", index, element);
+            }
+        }
+        ExportFormat::Json => println!("{}", serde_json::to_string_pretty(&elements).unwrap()),
+    }
+    Ok(())
+}
+
+pub fn snapshot(
+    config: &Config,
+    id: &str,
+    out: &Path,
+    csv: Option<PathBuf>,
+    authority: Option<String>,
+) -> Result<(), String> {
+    let snapshot = capture(config, id, authority)?;
+    fs::write(out, snapshot.to_bytes())
+        .map_err(|err| format!("Failed to write {}: {}", out.display(), err))?;
+    if let Some(csv) = csv {
+        fs::write(&csv, snapshot.to_csv())
+            .map_err(|err| format!("Failed to write {}: {}", csv.display(), err))?;
+    }
+
+    println!("List:     {}", snapshot.list);
+    println!("Slot:     {}", snapshot.slot);
+    println!("Elements: {}", snapshot.elements.len());
+    println!("SHA-256:  {}", hex(snapshot.hash().as_ref()));
+    Ok(())
+}
+
+pub fn diff(
+    config: &Config,
+    old: &Path,
+    new: Option<PathBuf>,
+    id: Option<String>,
+    authority: Option<String>,
+) -> Result<(), String> {
+    let old = load_snapshot(old)?;
+    let new = match (new, id) {
+        (Some(path), _) => load_snapshot(&path)?,
+        (None, Some(id)) => capture(config, &id, authority)?,
+        (None, None) => return Err("Pass a second snapshot or --id".to_string()),
+    };
+    if old.list != new.list {
+        eprintln!("Comparing different lists {} and {}", old.list, new.list);
+    }
+
+    let diff = diff_elements(&old.elements, &new.elements);
+    for index in &diff.changed {
+        let index = *index as usize;
+        println!(
+            "changed {}: {} -> {}",
+            index, old.elements[index], new.elements[index]
+        );
+    }
+    for index in &diff.added {
+        println!("added   {}: {}", index, new.elements[*index as usize]);
+    }
+    for index in &diff.removed {
+        println!("removed {}: {}", index, old.elements[*index as usize]);
+    }
+    if diff.is_empty() {
+        println!(
+            "Slots {} and {} hold the same {} elements",
+            old.slot,
+            new.slot,
+            new.elements.len()
+        );
+    }
+    Ok(())
+}
+
+pub fn trigger(cron: Option<String>, slot_interval: Option<u64>) -> BatchTrigger {
+    match (cron, slot_interval) {
+        (Some(schedule), _) => BatchTrigger::Cron { schedule },
+        (None, Some(interval)) => BatchTrigger::SlotInterval { interval },
+        (None, None) => BatchTrigger::Immediate,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn batch_start(
+    config: &Config,
+    id: &str,
+    batch_id: u64,
+    amount: u64,
+    batch_size: u8,
+    trigger: BatchTrigger,
+    shards: u8,
+    simulate: bool,
+) -> Result<(), String> {
+    let authority = config.keypair.pubkey();
+    let batch_process = derive_batch_process(&authority, id, batch_id);
+    // A sharded batch interrupted while starting its shards picks up where
+    // it stopped
+    let shards = if exists(config, &[batch_process])?[0] {
+        let shards = fetch_batch_process(config, &batch_process)?.shards;
+        if shards == 0 {
+            return Err(format!("Batch {} already exists", batch_process));
+        }
+        eprintln!(
+            "Batch {} already exists, resuming its shards",
+            batch_process
+        );
+        shards
+    } else {
+        let ix = initialize_batch_process(
+            &authority,
+            InitializeBatchProcess {
+                id: id.to_string(),
+                batch_id,
+                batch_size,
+                trigger,
+                amount,
+                action: BatchAction::Transfer,
+                shards,
+                simulate,
+            },
+        );
+        let signature = send(config, ix)?;
+        println!("Started batch {} in {}", batch_process, signature);
+        shards
+    };
+
+    let shard_keys: Vec<Pubkey> = (0..shards)
+        .map(|index| derive_shard(&batch_process, index))
+        .collect();
+    for (index, started) in exists(config, &shard_keys)?.into_iter().enumerate() {
+        if !started {
+            send(
+                config,
+                initialize_batch_shard(&authority, id, batch_id, index as u8),
+            )?;
+            eprintln!("Started shard {}/{}", index + 1, shards);
+        }
+    }
+    Ok(())
+}
+
+// Adds up the progress of a sharded batch, completing it once every shard
+// is done
+pub fn batch_sync(
+    config: &Config,
+    id: &str,
+    batch_id: u64,
+    authority: Option<String>,
+) -> Result<(), String> {
+    let authority = authority_or_keypair(config, authority)?;
+    let pubkey = derive_batch_process(&authority, id, batch_id);
+    let batch_process = fetch_batch_process(config, &pubkey)?;
+    if batch_process.shards == 0 {
+        return Err(format!("Batch {} is not sharded", pubkey));
+    }
+
+    let signature = send(
+        config,
+        sync_shards(&authority, id, batch_id, batch_process.shards),
+    )?;
+    let batch_process = fetch_batch_process(config, &pubkey)?;
+    println!(
+        "Synced {} in {}, {}/{} processed",
+        pubkey, signature, batch_process.total_processed, batch_process.total_elements
+    );
+    Ok(())
+}
+
+pub fn batch_status(
+    config: &Config,
+    id: &str,
+    batch_id: u64,
+    authority: Option<String>,
+) -> Result<(), String> {
+    let authority = authority_or_keypair(config, authority)?;
+    let pubkey = derive_batch_process(&authority, id, batch_id);
+    let batch_process = fetch_batch_process(config, &pubkey)?;
+
+    println!("Batch:      {}", pubkey);
+    println!("Status:     {:?}", batch_process.status);
+    println!("Mode:       {:?}", batch_process.mode);
+    println!("Trigger:    {:?}", batch_process.trigger);
+    println!("Amount:     {}", batch_process.amount);
+    println!(
+        "Processed:  {}/{}",
+        batch_process.total_processed, batch_process.total_elements
+    );
+    if batch_process.shards > 0 {
+        println!(
+            "Shards:     {}/{}",
+            batch_process.completed_shards, batch_process.shards
+        );
+    }
+    if batch_process.simulate {
+        println!("Simulated:  true");
+    }
+    Ok(())
+}
+
+// Subcommands the program has no instruction for
+pub fn unsupported(command: &str) -> Result<(), String> {
+    Err(format!(
+        "`{}` is unsupported by the program, it has no instruction for it",
+        command
+    ))
+}
+
+fn fetch_batch_process(config: &Config, pubkey: &Pubkey) -> Result<BatchProcess, String> {
+    let data = config
+        .rpc
+        .get_account_data(pubkey)
+        .map_err(|err| format!("Failed to fetch batch {}: {}", pubkey, err))?;
+    BatchProcess::try_deserialize(&mut data.as_slice())
+        .map_err(|_| format!("Account {} is not a batch process", pubkey))
+}
+
+// Whether each account exists, fetched in pages `getMultipleAccounts` takes
+fn exists(config: &Config, pubkeys: &[Pubkey]) -> Result<Vec<bool>, String> {
+    let mut exists = Vec::with_capacity(pubkeys.len());
+    for page in pubkeys.chunks(100) {
+        let accounts = config
+            .rpc
+            .get_multiple_accounts(page)
+            .map_err(|err| err.to_string())?;
+        exists.extend(accounts.iter().map(Option::is_some));
+    }
+    Ok(exists)
+}
+
+fn send(config: &Config, ix: Instruction) -> Result<Signature, String> {
+    send_all(config, &[ix])
+}
+
+fn send_all(config: &Config, ixs: &[Instruction]) -> Result<Signature, String> {
+    let blockhash = config
+        .rpc
+        .get_latest_blockhash()
+        .map_err(|err| err.to_string())?;
+    let tx = Transaction::new_signed_with_payer(
+        ixs,
+        Some(&config.keypair.pubkey()),
+        &[&config.keypair],
+        blockhash,
+    );
+    config
+        .rpc
+        .send_and_confirm_transaction(&tx)
+        .map_err(|err| format!("Transaction failed: {}", err))
+}
+
+fn authority_or_keypair(config: &Config, authority: Option<String>) -> Result<Pubkey, String> {
+    match authority {
+        Some(authority) => {
+            Pubkey::from_str(&authority).map_err(|_| format!("Invalid authority {}", authority))
+        }
+        None => Ok(config.keypair.pubkey()),
+    }
+}
+
+fn capture(config: &Config, id: &str, authority: Option<String>) -> Result<Snapshot, String> {
+    let authority = authority_or_keypair(config, authority)?;
+    let slot = config.rpc.get_slot().map_err(|err| err.to_string())?;
+    let mut reader = BigListReader::new(RpcFetcher(&config.rpc), authority, id);
+    Snapshot::capture(&mut reader, derive_root(&authority, id), slot).map_err(|err| err.to_string())
+}
+
+fn load_snapshot(path: &Path) -> Result<Snapshot, String> {
+    let bytes =
+        fs::read(path).map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+    Snapshot::from_bytes(&bytes).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+// Hex, as `sha256sum` prints it over the concatenated element bytes
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn open(path: &Path) -> Result<File, String> {
+    File::open(path).map_err(|err| format!("Failed to open {}: {}", path.display(), err))
+}
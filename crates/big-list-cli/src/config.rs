@@ -0,0 +1,67 @@
+use big_list_client::{AccountFetcher, Fetched};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    client_error::ClientError, rpc_client::RpcClient, rpc_config::RpcAccountInfoConfig,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair},
+};
+
+// Falls back to the Solana CLI config for anything not passed on the command line
+pub struct Config {
+    pub rpc: RpcClient,
+    pub keypair: Keypair,
+}
+
+impl Config {
+    pub fn load(url: Option<String>, keypair: Option<String>) -> Result<Config, String> {
+        let cli_config = match solana_cli_config::CONFIG_FILE.as_ref() {
+            Some(path) => solana_cli_config::Config::load(path).unwrap_or_default(),
+            None => solana_cli_config::Config::default(),
+        };
+        let url = url.unwrap_or(cli_config.json_rpc_url);
+        let keypair_path = keypair.unwrap_or(cli_config.keypair_path);
+        let keypair = read_keypair_file(&keypair_path)
+            .map_err(|err| format!("Failed to read keypair {}: {}", keypair_path, err))?;
+
+        Ok(Config {
+            rpc: RpcClient::new_with_commitment(url, CommitmentConfig::confirmed()),
+            keypair,
+        })
+    }
+}
+
+// Backs the list reader with `getMultipleAccounts`, base64 encoded since
+// list nodes are larger than base58 allows
+pub struct RpcFetcher<'a>(pub &'a RpcClient);
+
+impl<'a> AccountFetcher for RpcFetcher<'a> {
+    // Boxed, client errors are large
+    type Error = Box<ClientError>;
+
+    fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+        min_context_slot: Option<u64>,
+    ) -> Result<Fetched, Self::Error> {
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            min_context_slot,
+            ..RpcAccountInfoConfig::default()
+        };
+        let response = self
+            .0
+            .get_multiple_accounts_with_config(pubkeys, config)
+            .map_err(Box::new)?;
+        Ok(Fetched {
+            slot: response.context.slot,
+            accounts: response
+                .value
+                .into_iter()
+                .map(|account| account.map(|account| account.data))
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,266 @@
+mod commands;
+mod config;
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::config::Config;
+
+#[derive(Parser)]
+#[clap(name = "big-list", about = "Manage big lists from the terminal")]
+struct Cli {
+    /// RPC endpoint, defaults to the Solana CLI config
+    #[clap(long, short = 'u', global = true)]
+    url: Option<String>,
+    /// Authority keypair, defaults to the Solana CLI config
+    #[clap(long, short = 'k', global = true)]
+    keypair: Option<String>,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create an empty list
+    Create {
+        id: String,
+        /// Reject addresses already in the list
+        #[clap(long)]
+        unique: bool,
+        /// Require addresses to be appended in ascending order
+        #[clap(long)]
+        sorted: bool,
+    },
+    /// Append the addresses in the first column of a CSV or in a JSON
+    /// array, resuming an interrupted upload of the same file
+    Append {
+        id: String,
+        #[clap(long, required_unless_present = "json", conflicts_with = "json")]
+        csv: Option<PathBuf>,
+        #[clap(long)]
+        json: Option<PathBuf>,
+        /// Drop repeated addresses instead of appending them again
+        #[clap(long)]
+        dedupe: bool,
+    },
+    /// Print a list's root
+    Show {
+        id: String,
+        /// Owner of the list, defaults to the keypair
+        #[clap(long)]
+        authority: Option<String>,
+    },
+    /// Print every element of a list
+    Export {
+        id: String,
+        #[clap(long, value_enum, default_value = "csv")]
+        format: ExportFormat,
+        /// Owner of the list, defaults to the keypair
+        #[clap(long)]
+        authority: Option<String>,
+    },
+    /// Write a hashed binary snapshot of a list, and optionally a CSV of it
+    Snapshot {
+        id: String,
+        #[clap(long)]
+        out: PathBuf,
+        #[clap(long)]
+        csv: Option<PathBuf>,
+        /// Owner of the list, defaults to the keypair
+        #[clap(long)]
+        authority: Option<String>,
+    },
+    /// Compare a snapshot with another one or with the live list
+    Diff {
+        old: PathBuf,
+        #[clap(required_unless_present = "id", conflicts_with = "id")]
+        new: Option<PathBuf>,
+        /// Compare against this list on chain
+        #[clap(long)]
+        id: Option<String>,
+        /// Owner of the list, defaults to the keypair
+        #[clap(long)]
+        authority: Option<String>,
+    },
+    /// Remove an address from a list. Unsupported by the program.
+    Remove { id: String, address: String },
+    /// Replace the element at an index. Unsupported by the program.
+    Set {
+        id: String,
+        index: u32,
+        address: String,
+    },
+    /// Stop a list from taking more elements. Unsupported by the program.
+    Freeze { id: String },
+    /// Close a list and reclaim its rent. Unsupported by the program.
+    Close { id: String },
+    /// Distribute to a list
+    Batch {
+        #[clap(subcommand)]
+        command: BatchCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum BatchCommand {
+    /// Start a push distribution of `amount` lamports per element
+    Start {
+        id: String,
+        #[clap(long)]
+        batch_id: u64,
+        #[clap(long)]
+        amount: u64,
+        #[clap(long, default_value = "12")]
+        batch_size: u8,
+        /// Cron schedule, batches chain immediately when neither this nor
+        /// --slot-interval is set
+        #[clap(long, conflicts_with = "slot-interval")]
+        cron: Option<String>,
+        #[clap(long)]
+        slot_interval: Option<u64>,
+        #[clap(long, default_value = "0")]
+        shards: u8,
+        /// Emit what would be paid without moving funds
+        #[clap(long)]
+        simulate: bool,
+    },
+    /// Add up the progress of a sharded batch's shards
+    Sync {
+        id: String,
+        #[clap(long)]
+        batch_id: u64,
+        /// Owner of the list, defaults to the keypair
+        #[clap(long)]
+        authority: Option<String>,
+    },
+    /// Pause a batch. Unsupported by the program.
+    Pause {
+        id: String,
+        #[clap(long)]
+        batch_id: u64,
+    },
+    /// Cancel a batch and reclaim its vault. Unsupported by the program.
+    Cancel {
+        id: String,
+        #[clap(long)]
+        batch_id: u64,
+    },
+    /// Print a batch's progress
+    Status {
+        id: String,
+        #[clap(long)]
+        batch_id: u64,
+        /// Owner of the list, defaults to the keypair
+        #[clap(long)]
+        authority: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    // Commands the program has no instruction for fail before the config is
+    // loaded, so they don't ask for a keypair first
+    let unsupported = match &cli.command {
+        Command::Remove { .. } => Some("remove"),
+        Command::Set { .. } => Some("set"),
+        Command::Freeze { .. } => Some("freeze"),
+        Command::Close { .. } => Some("close"),
+        Command::Batch {
+            command: BatchCommand::Pause { .. },
+        } => Some("batch pause"),
+        Command::Batch {
+            command: BatchCommand::Cancel { .. },
+        } => Some("batch cancel"),
+        _ => None,
+    };
+    let result = match unsupported {
+        Some(command) => commands::unsupported(command),
+        None => Config::load(cli.url, cli.keypair).and_then(|config| run(config, cli.command)),
+    };
+    if let Err(err) = result {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(config: Config, command: Command) -> Result<(), String> {
+    match command {
+        Command::Create { id, unique, sorted } => commands::create(&config, &id, unique, sorted),
+        Command::Append {
+            id,
+            csv,
+            json,
+            dedupe,
+        } => commands::append(&config, &id, csv, json, dedupe),
+        Command::Show { id, authority } => commands::show(&config, &id, authority),
+        Command::Export {
+            id,
+            format,
+            authority,
+        } => commands::export(&config, &id, format, authority),
+        Command::Snapshot {
+            id,
+            out,
+            csv,
+            authority,
+        } => commands::snapshot(&config, &id, &out, csv, authority),
+        Command::Diff {
+            old,
+            new,
+            id,
+            authority,
+        } => commands::diff(&config, &old, new, id, authority),
+        Command::Batch {
+            command:
+                BatchCommand::Start {
+                    id,
+                    batch_id,
+                    amount,
+                    batch_size,
+                    cron,
+                    slot_interval,
+                    shards,
+                    simulate,
+                },
+        } => commands::batch_start(
+            &config,
+            &id,
+            batch_id,
+            amount,
+            batch_size,
+            commands::trigger(cron, slot_interval),
+            shards,
+            simulate,
+        ),
+        Command::Batch {
+            command:
+                BatchCommand::Status {
+                    id,
+                    batch_id,
+                    authority,
+                },
+        } => commands::batch_status(&config, &id, batch_id, authority),
+        Command::Batch {
+            command:
+                BatchCommand::Sync {
+                    id,
+                    batch_id,
+                    authority,
+                },
+        } => commands::batch_sync(&config, &id, batch_id, authority),
+        Command::Remove { .. }
+        | Command::Set { .. }
+        | Command::Freeze { .. }
+        | Command::Close { .. }
+        | Command::Batch {
+            command: BatchCommand::Pause { .. } | BatchCommand::Cancel { .. },
+        } => unreachable!("unsupported commands are rejected before the config loads"),
+    }
+}
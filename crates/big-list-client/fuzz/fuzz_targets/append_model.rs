@@ -0,0 +1,35 @@
+#![no_main]
+
+use anchor_lang::prelude::Pubkey;
+use big_list_client::{plan_chunks, ListModel};
+use libfuzzer_sys::fuzz_target;
+
+// Drives the list model with arbitrary appends. The instruction the planner
+// picks for a chunk has to be accepted, a plain append is rejected whenever
+// it picks a rollover, and accepted appends keep every leaf but the last full.
+fuzz_target!(|sizes: Vec<u8>| {
+    let mut model = ListModel::default();
+    for size in sizes {
+        let count = size as usize % 64 + 1;
+        let addresses: Vec<Pubkey> = (0..count).map(|_| Pubkey::new_unique()).collect();
+        let step = plan_chunks(model.total_elements, &[count])[0];
+
+        let planned = if step.rollover {
+            assert!(model.clone().append(&addresses).is_err());
+            model.append_rollover_k(&addresses)
+        } else {
+            model.append(&addresses)
+        };
+        if planned.is_err() {
+            // Only a chunk spilling past the next leaf or into a new branch
+            assert!(step.rollover);
+            return;
+        }
+
+        let leaves: Vec<&Vec<Pubkey>> = model.leaves.values().collect();
+        assert!(leaves[..leaves.len() - 1]
+            .iter()
+            .all(|leaf| leaf.len() == 256));
+        assert_eq!(model.elements().len() as u32, model.total_elements);
+    }
+});
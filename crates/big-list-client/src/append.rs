@@ -0,0 +1,153 @@
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction};
+use big_list_core::needs_rollover;
+
+use crate::instructions::{append, append_rollover_k, ListRef};
+
+// Addresses per append, what fits in a transaction next to its accounts
+pub const APPEND_CHUNK: usize = 25;
+// Unique lists also pass up to one bucket per address
+pub const UNIQUE_APPEND_CHUNK: usize = 12;
+
+// One append of the plan, `addresses[offset..offset + count]` of the input
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AppendStep {
+    pub offset: usize,
+    pub count: usize,
+    // Index of the first appended element in the list
+    pub start_index: u32,
+    pub rollover: bool,
+}
+
+impl AppendStep {
+    pub fn instruction(&self, list: &ListRef, addresses: &[Pubkey]) -> Instruction {
+        let chunk = addresses[self.offset..self.offset + self.count].to_vec();
+        if self.rollover {
+            append_rollover_k(list, chunk)
+        } else {
+            append(list, chunk)
+        }
+    }
+}
+
+// Splits `count` addresses into appends starting from `total_elements`,
+// rolling over to a new leaf whenever a chunk does not fit in the current one
+pub fn plan_appends(total_elements: u32, count: usize, unique: bool) -> Vec<AppendStep> {
+    let chunk_size = if unique {
+        UNIQUE_APPEND_CHUNK
+    } else {
+        APPEND_CHUNK
+    };
+    let sizes: Vec<usize> = (0..count)
+        .step_by(chunk_size)
+        .map(|offset| chunk_size.min(count - offset))
+        .collect();
+    plan_chunks(total_elements, &sizes)
+}
+
+// Like `plan_appends` with the size of every chunk given
+pub fn plan_chunks(total_elements: u32, sizes: &[usize]) -> Vec<AppendStep> {
+    let mut total = total_elements;
+    let mut offset = 0;
+
+    sizes
+        .iter()
+        .map(|count| {
+            let step = AppendStep {
+                offset,
+                count: *count,
+                start_index: total,
+                rollover: needs_rollover(total, *count),
+            };
+            total += *count as u32;
+            offset += count;
+            step
+        })
+        .collect()
+}
+
+// The instructions of `plan_appends`. They have to land in order,
+// `list.total_elements` ends up past the last one.
+pub fn append_all(list: &mut ListRef, addresses: &[Pubkey]) -> Vec<Instruction> {
+    plan_appends(list.total_elements, addresses.len(), list.unique)
+        .iter()
+        .map(|step| {
+            let ix = step.instruction(list, addresses);
+            list.total_elements += step.count as u32;
+            ix
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+
+    fn gen_list(total_elements: u32) -> ListRef {
+        ListRef {
+            authority: Pubkey::new_unique(),
+            id: "my_big_list".to_string(),
+            total_elements,
+            unique: false,
+        }
+    }
+
+    #[test]
+    pub fn it_rolls_over_when_a_chunk_leaves_the_leaf() {
+        let addresses: Vec<Pubkey> = (0..100).map(|_| Pubkey::new_unique()).collect();
+        let mut list = gen_list(200);
+        let ixs = append_all(&mut list, &addresses);
+
+        let rollovers: Vec<bool> = ixs
+            .iter()
+            .map(|ix| ix.data[..8] == big_list::instruction::AppendRolloverK::DISCRIMINATOR)
+            .collect();
+        // 200 + 25 + 25 = 250, the third chunk spills into the next leaf
+        assert_eq!(rollovers, vec![false, false, true, false]);
+        assert_eq!(list.total_elements, 300);
+    }
+
+    #[test]
+    pub fn it_plans_from_the_current_length() {
+        let steps = plan_appends(240, 30, true);
+        assert_eq!(
+            steps,
+            vec![
+                AppendStep {
+                    offset: 0,
+                    count: 12,
+                    start_index: 240,
+                    rollover: false
+                },
+                AppendStep {
+                    offset: 12,
+                    count: 12,
+                    start_index: 252,
+                    rollover: true
+                },
+                AppendStep {
+                    offset: 24,
+                    count: 6,
+                    start_index: 264,
+                    rollover: false
+                },
+            ]
+        );
+    }
+
+    #[test]
+    pub fn it_rolls_over_from_a_full_leaf() {
+        let addresses = vec![Pubkey::new_unique()];
+        let ixs = append_all(&mut gen_list(256), &addresses);
+        assert_eq!(
+            &ixs[0].data[..8],
+            &big_list::instruction::AppendRolloverK::DISCRIMINATOR
+        );
+
+        let ixs = append_all(&mut gen_list(0), &addresses);
+        assert_eq!(
+            &ixs[0].data[..8],
+            &big_list::instruction::Append::DISCRIMINATOR
+        );
+    }
+}
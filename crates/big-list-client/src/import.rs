@@ -0,0 +1,210 @@
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anchor_lang::{prelude::Pubkey, solana_program::hash::hashv};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq)]
+pub enum ImportError {
+    Read(String),
+    // `row` is 1-based, counting a skipped header
+    InvalidAddress { row: usize, value: String },
+    Checkpoint(String),
+    // The list grew past what this import would have appended
+    Diverged { uploaded: usize, addresses: usize },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Read(err) => write!(f, "Failed to read input: {}", err),
+            ImportError::InvalidAddress { row, value } => {
+                write!(f, "Row {} holds an invalid address {:?}", row, value)
+            }
+            ImportError::Checkpoint(err) => write!(f, "Checkpoint: {}", err),
+            ImportError::Diverged {
+                uploaded,
+                addresses,
+            } => write!(
+                f,
+                "List grew by {} elements since the import started but it only has {}",
+                uploaded, addresses
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+// Raw values from the first column, a header row is kept for `normalize` to skip
+pub fn read_csv<R: Read>(reader: R) -> Result<Vec<String>, ImportError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader);
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(|err| ImportError::Read(err.to_string()))?;
+            Ok(record.get(0).unwrap_or_default().to_string())
+        })
+        .collect()
+}
+
+// A JSON array of address strings
+pub fn read_json<R: Read>(reader: R) -> Result<Vec<String>, ImportError> {
+    serde_json::from_reader(reader).map_err(|err| ImportError::Read(err.to_string()))
+}
+
+// Parses every value as a pubkey, trimming whitespace and quotes. A first
+// row that doesn't parse is taken for a header. With `dedupe` only the
+// first occurrence of an address is kept.
+pub fn normalize(values: &[String], dedupe: bool) -> Result<Vec<Pubkey>, ImportError> {
+    let mut seen = HashSet::new();
+    let mut addresses = Vec::with_capacity(values.len());
+    for (row, value) in values.iter().enumerate() {
+        let trimmed = value.trim().trim_matches(|c| c == '"' || c == '\'');
+        let address = match Pubkey::from_str(trimmed) {
+            Ok(address) => address,
+            Err(_) if row == 0 => continue,
+            Err(_) => {
+                return Err(ImportError::InvalidAddress {
+                    row: row + 1,
+                    value: value.clone(),
+                })
+            }
+        };
+        if dedupe && !seen.insert(address) {
+            continue;
+        }
+        addresses.push(address);
+    }
+    Ok(addresses)
+}
+
+// Kept on disk while an import runs. Appends are not idempotent, so a
+// rerun resumes from however far the list got past `start_total`.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Checkpoint {
+    pub list: String,
+    pub start_total: u32,
+    // Hash of the addresses, so a changed input doesn't resume the old import
+    pub fingerprint: String,
+}
+
+impl Checkpoint {
+    // The default checkpoint of an input file
+    pub fn path(input: &Path) -> PathBuf {
+        let mut path = input.as_os_str().to_owned();
+        path.push(".progress");
+        PathBuf::from(path)
+    }
+
+    pub fn fingerprint(addresses: &[Pubkey]) -> String {
+        let bytes: Vec<&[u8]> = addresses.iter().map(|address| address.as_ref()).collect();
+        hashv(&bytes).to_string()
+    }
+
+    // Resumes the checkpoint at `path`, or starts one from `total_elements`
+    pub fn load_or_start(
+        path: &Path,
+        list: &Pubkey,
+        total_elements: u32,
+        addresses: &[Pubkey],
+    ) -> Result<Checkpoint, ImportError> {
+        let fingerprint = Checkpoint::fingerprint(addresses);
+        if let Ok(data) = fs::read_to_string(path) {
+            let checkpoint: Checkpoint = serde_json::from_str(&data).map_err(|err| {
+                ImportError::Checkpoint(format!("{} is invalid: {}", path.display(), err))
+            })?;
+            if checkpoint.list != list.to_string() {
+                return Err(ImportError::Checkpoint(format!(
+                    "{} belongs to list {}",
+                    path.display(),
+                    checkpoint.list
+                )));
+            }
+            if checkpoint.fingerprint != fingerprint {
+                return Err(ImportError::Checkpoint(format!(
+                    "{} was written for different addresses",
+                    path.display()
+                )));
+            }
+            return Ok(checkpoint);
+        }
+
+        let checkpoint = Checkpoint {
+            list: list.to_string(),
+            start_total: total_elements,
+            fingerprint,
+        };
+        fs::write(path, serde_json::to_string(&checkpoint).unwrap()).map_err(|err| {
+            ImportError::Checkpoint(format!("Failed to write {}: {}", path.display(), err))
+        })?;
+        Ok(checkpoint)
+    }
+
+    // How many of the addresses are already on chain
+    pub fn uploaded(&self, total_elements: u32, addresses: usize) -> Result<usize, ImportError> {
+        let uploaded = total_elements.saturating_sub(self.start_total) as usize;
+        if uploaded > addresses {
+            return Err(ImportError::Diverged {
+                uploaded,
+                addresses,
+            });
+        }
+        Ok(uploaded)
+    }
+
+    pub fn finish(path: &Path) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn it_normalizes_and_dedupes() {
+        let address = Pubkey::new_unique();
+        let input = format!("address,amount\n {},1\n\"{}\",2\n", address, address);
+        let values = read_csv(input.as_bytes()).unwrap();
+
+        assert_eq!(normalize(&values, false).unwrap(), vec![address, address]);
+        assert_eq!(normalize(&values, true).unwrap(), vec![address]);
+
+        let values = vec![address.to_string(), "not-a-key".to_string()];
+        assert_eq!(
+            normalize(&values, false),
+            Err(ImportError::InvalidAddress {
+                row: 2,
+                value: "not-a-key".to_string()
+            })
+        );
+    }
+
+    #[test]
+    pub fn it_resumes_from_the_on_chain_length() {
+        let path = std::env::temp_dir().join(format!("big-list-{}.progress", std::process::id()));
+        let list = Pubkey::new_unique();
+        let addresses: Vec<Pubkey> = (0..100).map(|_| Pubkey::new_unique()).collect();
+
+        let checkpoint = Checkpoint::load_or_start(&path, &list, 40, &addresses).unwrap();
+        assert_eq!(checkpoint.uploaded(40, addresses.len()), Ok(0));
+
+        // The list grew by 75 before the import was interrupted
+        let checkpoint = Checkpoint::load_or_start(&path, &list, 115, &addresses).unwrap();
+        assert_eq!(checkpoint.uploaded(115, addresses.len()), Ok(75));
+        assert!(checkpoint.uploaded(150, addresses.len()).is_err());
+        assert!(Checkpoint::load_or_start(&path, &list, 115, &addresses[1..]).is_err());
+
+        Checkpoint::finish(&path);
+        assert!(!path.exists());
+    }
+}
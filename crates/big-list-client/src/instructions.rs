@@ -0,0 +1,233 @@
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        system_program,
+    },
+    InstructionData, ToAccountMetas,
+};
+use big_list::{
+    accounts, instruction,
+    state::{BatchFailures, BatchProcess, IndexType},
+};
+use big_list_core::{get_j, get_k};
+
+use crate::pda::{
+    derive_batch_process, derive_batch_thread, derive_bucket, derive_j, derive_k, derive_root,
+    derive_shard, derive_shard_thread,
+};
+
+// What the builders need to know about a list to derive its accounts
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListRef {
+    pub authority: Pubkey,
+    pub id: String,
+    pub total_elements: u32,
+    pub unique: bool,
+}
+
+impl ListRef {
+    pub fn root(&self) -> Pubkey {
+        derive_root(&self.authority, &self.id)
+    }
+
+    // The branch and leaf `append` writes to, the leaf of the last element
+    pub fn current_leaf(&self) -> (Pubkey, Pubkey) {
+        let (j, k) = (get_j(self.total_elements), get_k(self.total_elements));
+        (
+            derive_j(&self.authority, &self.id, j),
+            derive_k(&self.authority, &self.id, j, k),
+        )
+    }
+
+    // Unique lists take the bucket of every distinct prefix being appended
+    pub fn buckets(&self, addresses: &[Pubkey]) -> Vec<AccountMeta> {
+        if !self.unique {
+            return vec![];
+        }
+        let mut prefixes: Vec<u8> = addresses
+            .iter()
+            .map(|address| address.to_bytes()[0])
+            .collect();
+        prefixes.sort_unstable();
+        prefixes.dedup();
+        prefixes
+            .into_iter()
+            .map(|prefix| AccountMeta::new(derive_bucket(&self.root(), prefix), false))
+            .collect()
+    }
+}
+
+pub fn initialize(
+    authority: &Pubkey,
+    signer: &Pubkey,
+    id: &str,
+    depth: u8,
+    unique: bool,
+    index_type: IndexType,
+) -> Instruction {
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::InitializeBigList {
+            big_list: derive_root(authority, id),
+            big_list_j: derive_j(authority, id, 0),
+            big_list_k: derive_k(authority, id, 0, 0),
+            authority: *authority,
+            signer: *signer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Initialize {
+            id: id.to_string(),
+            depth,
+            unique,
+            index_type,
+        }
+        .data(),
+    }
+}
+
+// Unique lists need the bucket of every prefix, 256 of them, before their
+// first append
+pub fn initialize_bucket(authority: &Pubkey, id: &str, prefix: u8) -> Instruction {
+    let big_list = derive_root(authority, id);
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::InitializeBucket {
+            big_list,
+            bucket: derive_bucket(&big_list, prefix),
+            authority: *authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeBucket {
+            id: id.to_string(),
+            prefix,
+        }
+        .data(),
+    }
+}
+
+// Appends addresses that fit in the current leaf
+pub fn append(list: &ListRef, addresses: Vec<Pubkey>) -> Instruction {
+    let (big_list_j, big_list_k) = list.current_leaf();
+    let mut account_metas = accounts::Append {
+        big_list: list.root(),
+        big_list_j,
+        big_list_k,
+        authority: list.authority,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    account_metas.extend(list.buckets(&addresses));
+
+    Instruction {
+        program_id: big_list::ID,
+        accounts: account_metas,
+        data: instruction::Append {
+            id: list.id.clone(),
+            addresses,
+        }
+        .data(),
+    }
+}
+
+// Fills the current leaf and opens the next one with the rest
+pub fn append_rollover_k(list: &ListRef, addresses: Vec<Pubkey>) -> Instruction {
+    let (big_list_j, big_list_k) = list.current_leaf();
+    let next_total = list.total_elements + addresses.len() as u32;
+    let mut account_metas = accounts::AppendRolloverK {
+        big_list: list.root(),
+        big_list_j,
+        big_list_k,
+        big_list_k_next: derive_k(
+            &list.authority,
+            &list.id,
+            get_j(next_total),
+            get_k(next_total),
+        ),
+        authority: list.authority,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    account_metas.extend(list.buckets(&addresses));
+
+    Instruction {
+        program_id: big_list::ID,
+        accounts: account_metas,
+        data: instruction::AppendRolloverK {
+            id: list.id.clone(),
+            addresses,
+        }
+        .data(),
+    }
+}
+
+pub fn initialize_batch_process(
+    authority: &Pubkey,
+    args: instruction::InitializeBatchProcess,
+) -> Instruction {
+    let batch_process = derive_batch_process(authority, &args.id, args.batch_id);
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::InitializeBatchProcess {
+            batch_process,
+            big_list: derive_root(authority, &args.id),
+            big_list_j: derive_j(authority, &args.id, 0),
+            big_list_k: derive_k(authority, &args.id, 0, 0),
+            failures: BatchFailures::pubkey(batch_process),
+            vault: BatchProcess::vault(batch_process),
+            authority: *authority,
+            batch_processor_thread: derive_batch_thread(&batch_process, args.batch_id),
+            thread_program: clockwork_sdk::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+// Starts the thread of shard `index`, once per shard after
+// `initialize_batch_process`
+pub fn initialize_batch_shard(
+    authority: &Pubkey,
+    id: &str,
+    batch_id: u64,
+    index: u8,
+) -> Instruction {
+    let batch_process = derive_batch_process(authority, id, batch_id);
+    let shard = derive_shard(&batch_process, index);
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::InitializeBatchShard {
+            batch_process,
+            shard,
+            failures: BatchFailures::pubkey(shard),
+            vault: BatchProcess::vault(batch_process),
+            shard_vault: BatchProcess::vault(shard),
+            big_list: derive_root(authority, id),
+            authority: *authority,
+            shard_thread: derive_shard_thread(&batch_process, batch_id, index),
+            thread_program: clockwork_sdk::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeBatchShard { index }.data(),
+    }
+}
+
+// Adds up the progress of every shard on the batch process. Anyone can
+// send it.
+pub fn sync_shards(authority: &Pubkey, id: &str, batch_id: u64, shards: u8) -> Instruction {
+    let batch_process = derive_batch_process(authority, id, batch_id);
+    let mut account_metas = accounts::SyncShards { batch_process }.to_account_metas(None);
+    account_metas.extend(
+        (0..shards)
+            .map(|index| AccountMeta::new_readonly(derive_shard(&batch_process, index), false)),
+    );
+    Instruction {
+        program_id: big_list::ID,
+        accounts: account_metas,
+        data: instruction::SyncShards {}.data(),
+    }
+}
@@ -0,0 +1,17 @@
+pub mod append;
+pub mod import;
+pub mod instructions;
+pub mod model;
+pub mod pda;
+pub mod reader;
+pub mod snapshot;
+
+pub use append::*;
+pub use import::*;
+pub use instructions::*;
+pub use model::*;
+pub use pda::*;
+pub use reader::*;
+pub use snapshot::*;
+
+pub use big_list_core::{get_current_indices, get_total};
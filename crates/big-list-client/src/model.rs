@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+
+use anchor_lang::prelude::Pubkey;
+use big_list_core::{get_j, get_k, split_rollover, LEAF_CAPACITY};
+
+use crate::append::AppendStep;
+
+// Off-chain model of where `append` and `append_rollover_k` put elements,
+// used to check plans before sending them. Errors stand for the cases the
+// program rejects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListModel {
+    pub total_elements: u32,
+    // Leaves by (j, k)
+    pub leaves: BTreeMap<(u8, u8), Vec<Pubkey>>,
+}
+
+impl Default for ListModel {
+    // A freshly initialized list, with its first leaf empty
+    fn default() -> Self {
+        ListModel {
+            total_elements: 0,
+            leaves: BTreeMap::from([((0, 0), vec![])]),
+        }
+    }
+}
+
+impl ListModel {
+    pub fn append(&mut self, addresses: &[Pubkey]) -> Result<(), String> {
+        let leaf = self.current_leaf()?;
+        if leaf.len() + addresses.len() > LEAF_CAPACITY {
+            return Err(format!("{} elements overflow the leaf", addresses.len()));
+        }
+        leaf.extend_from_slice(addresses);
+        self.total_elements += addresses.len() as u32;
+        Ok(())
+    }
+
+    pub fn append_rollover_k(&mut self, addresses: &[Pubkey]) -> Result<(), String> {
+        let j = get_j(self.total_elements);
+        let next_total = self.total_elements + addresses.len() as u32;
+        let next = (get_j(next_total), get_k(next_total));
+        if next.0 != j {
+            return Err("Rolling over into a new branch is not supported".to_string());
+        }
+        if self.leaves.contains_key(&next) {
+            return Err(format!("Leaf {:?} already exists", next));
+        }
+
+        let leaf = self.current_leaf()?;
+        let (remaining, _) = split_rollover(leaf.len(), addresses.len()).ok_or(format!(
+            "{} elements don't roll over into one leaf",
+            addresses.len()
+        ))?;
+        leaf.extend_from_slice(&addresses[..remaining]);
+        self.leaves.insert(next, addresses[remaining..].to_vec());
+        self.total_elements = next_total;
+        Ok(())
+    }
+
+    pub fn apply(&mut self, step: &AppendStep, addresses: &[Pubkey]) -> Result<(), String> {
+        if step.start_index != self.total_elements {
+            return Err(format!(
+                "Step starts at {} but the list holds {}",
+                step.start_index, self.total_elements
+            ));
+        }
+        let chunk = &addresses[step.offset..step.offset + step.count];
+        if step.rollover {
+            self.append_rollover_k(chunk)
+        } else {
+            self.append(chunk)
+        }
+    }
+
+    // Every element in global index order
+    pub fn elements(&self) -> Vec<Pubkey> {
+        self.leaves.values().flatten().cloned().collect()
+    }
+
+    fn current_leaf(&mut self) -> Result<&mut Vec<Pubkey>, String> {
+        let at = (get_j(self.total_elements), get_k(self.total_elements));
+        self.leaves
+            .get_mut(&at)
+            .ok_or(format!("Leaf {:?} does not exist", at))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::append::plan_chunks;
+    use proptest::prelude::*;
+
+    fn one_at_a_time(addresses: &[Pubkey]) -> ListModel {
+        let mut model = ListModel::default();
+        for step in plan_chunks(0, &vec![1; addresses.len()]) {
+            model.apply(&step, addresses).unwrap();
+        }
+        model
+    }
+
+    proptest! {
+        #[test]
+        fn it_lays_out_chunks_like_single_appends(
+            prefilled in 0..600usize,
+            sizes in prop::collection::vec(1..=25usize, 0..40),
+        ) {
+            let count = prefilled + sizes.iter().sum::<usize>();
+            let addresses: Vec<Pubkey> = (0..count).map(|_| Pubkey::new_unique()).collect();
+
+            let mut model = one_at_a_time(&addresses[..prefilled]);
+            let steps = plan_chunks(prefilled as u32, &sizes);
+            for step in &steps {
+                let step = AppendStep { offset: step.offset + prefilled, ..*step };
+                model.apply(&step, &addresses).unwrap();
+            }
+
+            prop_assert_eq!(&model, &one_at_a_time(&addresses));
+            // Every leaf but the last is full
+            let leaves: Vec<Vec<Pubkey>> = model.leaves.values().cloned().collect();
+            let expected: Vec<Vec<Pubkey>> = addresses.chunks(256).map(|leaf| leaf.to_vec()).collect();
+            if count > 0 {
+                prop_assert_eq!(leaves, expected);
+            }
+        }
+    }
+
+    #[test]
+    pub fn it_rejects_misplanned_steps() {
+        let addresses: Vec<Pubkey> = (0..10).map(|_| Pubkey::new_unique()).collect();
+        let mut model = ListModel::default();
+        assert!(model.append_rollover_k(&addresses).is_err());
+
+        model.total_elements = 250;
+        model.leaves.insert((0, 0), vec![Pubkey::default(); 250]);
+        assert!(model.append(&addresses).is_err());
+        assert!(model.append_rollover_k(&addresses).is_ok());
+        assert_eq!(model.leaves[&(0, 1)].len(), 4);
+    }
+}
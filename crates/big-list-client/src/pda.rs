@@ -0,0 +1,59 @@
+use anchor_lang::prelude::Pubkey;
+use big_list::state::{BatchProcess, BatchShard, BigList, SetBucket};
+use clockwork_sdk::state::Thread;
+
+pub fn derive_root(authority: &Pubkey, id: &str) -> Pubkey {
+    BigList::pubkey(*authority, id, &[])
+}
+
+pub fn derive_j(authority: &Pubkey, id: &str, j: u8) -> Pubkey {
+    BigList::pubkey(*authority, id, &[j])
+}
+
+pub fn derive_k(authority: &Pubkey, id: &str, j: u8, k: u8) -> Pubkey {
+    BigList::pubkey(*authority, id, &[j, k])
+}
+
+pub fn derive_bucket(big_list: &Pubkey, prefix: u8) -> Pubkey {
+    SetBucket::pubkey(*big_list, prefix)
+}
+
+pub fn derive_batch_process(authority: &Pubkey, id: &str, batch_id: u64) -> Pubkey {
+    BatchProcess::pubkey(*authority, id, batch_id)
+}
+
+pub fn derive_shard(batch_process: &Pubkey, index: u8) -> Pubkey {
+    BatchShard::pubkey(*batch_process, index)
+}
+
+pub fn derive_batch_thread(batch_process: &Pubkey, batch_id: u64) -> Pubkey {
+    Thread::pubkey(*batch_process, batch_id.to_string())
+}
+
+pub fn derive_shard_thread(batch_process: &Pubkey, batch_id: u64, index: u8) -> Pubkey {
+    Thread::pubkey(*batch_process, BatchShard::thread_id(batch_id, index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn it_derives_the_program_seeds() {
+        let authority = Pubkey::new_unique();
+        let (k, _) = Pubkey::find_program_address(
+            &[b"3", b"1", b"my_big_list", authority.as_ref()],
+            &big_list::ID,
+        );
+        assert_eq!(derive_k(&authority, "my_big_list", 1, 3), k);
+
+        let (root, _) =
+            Pubkey::find_program_address(&[b"my_big_list", authority.as_ref()], &big_list::ID);
+        assert_eq!(derive_root(&authority, "my_big_list"), root);
+
+        let batch_process = Pubkey::new_unique();
+        let (shard, _) =
+            Pubkey::find_program_address(&[b"shard", batch_process.as_ref(), &[2]], &big_list::ID);
+        assert_eq!(derive_shard(&batch_process, 2), shard);
+    }
+}
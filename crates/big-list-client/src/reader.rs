@@ -0,0 +1,396 @@
+use std::{cell::Cell, collections::HashMap, convert::Infallible, fmt};
+
+use anchor_lang::{prelude::Pubkey, AccountDeserialize};
+use big_list::state::BigList;
+
+use crate::pda::{derive_j, derive_k, derive_root};
+
+// The most accounts `getMultipleAccounts` returns in one call
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+// Where the reader loads accounts from. An `RpcClient` backs it with
+// `get_multiple_accounts`, keeping only the account data and the slot the
+// node read it at.
+pub trait AccountFetcher {
+    type Error;
+
+    // Nodes behind `min_context_slot` refuse the call rather than serve
+    // older accounts
+    fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+        min_context_slot: Option<u64>,
+    ) -> Result<Fetched, Self::Error>;
+}
+
+// Accounts of one fetch, `None` where they don't exist
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fetched {
+    pub slot: u64,
+    pub accounts: Vec<Option<Vec<u8>>>,
+}
+
+// In-memory account store, keyed by address. It has no slots, so it reads
+// at the slot asked for.
+impl AccountFetcher for HashMap<Pubkey, Vec<u8>> {
+    type Error = Infallible;
+
+    fn get_multiple_accounts(
+        &self,
+        pubkeys: &[Pubkey],
+        min_context_slot: Option<u64>,
+    ) -> Result<Fetched, Infallible> {
+        Ok(Fetched {
+            slot: min_context_slot.unwrap_or(0),
+            accounts: pubkeys
+                .iter()
+                .map(|pubkey| self.get(pubkey).cloned())
+                .collect(),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ReaderError<E> {
+    Fetch(E),
+    AccountNotFound(Pubkey),
+    InvalidAccount(Pubkey),
+    // A node points at something other than the child derived for `index`
+    BrokenLink {
+        node: Pubkey,
+        index: u8,
+    },
+    // A node's `total_elements` differs from what its children hold
+    TotalMismatch {
+        node: Pubkey,
+        expected: u32,
+        found: u32,
+    },
+    // Fetches were read at different slots, so they may not agree
+    SlotMismatch {
+        lowest: u64,
+        highest: u64,
+    },
+}
+
+impl<E: fmt::Debug> fmt::Display for ReaderError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReaderError::Fetch(err) => write!(f, "Failed to fetch accounts: {:?}", err),
+            ReaderError::AccountNotFound(pubkey) => write!(f, "Account {} not found", pubkey),
+            ReaderError::InvalidAccount(pubkey) => {
+                write!(f, "Account {} is not a big list node", pubkey)
+            }
+            ReaderError::BrokenLink { node, index } => {
+                write!(f, "Node {} does not link child {}", node, index)
+            }
+            ReaderError::TotalMismatch {
+                node,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Node {} counts {} elements but its children hold {}",
+                node, expected, found
+            ),
+            ReaderError::SlotMismatch { lowest, highest } => write!(
+                f,
+                "Accounts were read across slots {} to {} rather than at one slot",
+                lowest, highest
+            ),
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for ReaderError<E> {}
+
+// Reads a whole list back in global index order
+pub struct BigListReader<F> {
+    fetcher: F,
+    authority: Pubkey,
+    id: String,
+    min_context_slot: Option<u64>,
+    // Lowest and highest slot fetched at since the last `take_context_slots`
+    context_slots: Cell<Option<(u64, u64)>>,
+}
+
+impl<F: AccountFetcher> BigListReader<F> {
+    pub fn new(fetcher: F, authority: Pubkey, id: &str) -> Self {
+        BigListReader {
+            fetcher,
+            authority,
+            id: id.to_string(),
+            min_context_slot: None,
+            context_slots: Cell::new(None),
+        }
+    }
+
+    // Every later fetch reads at this slot or after
+    pub fn set_min_context_slot(&mut self, slot: Option<u64>) {
+        self.min_context_slot = slot;
+    }
+
+    // The lowest and highest slot fetched at since the last call, equal
+    // when every account was read at the same slot
+    pub fn take_context_slots(&self) -> Option<(u64, u64)> {
+        self.context_slots.take()
+    }
+
+    pub fn root(&self) -> Result<BigList, ReaderError<F::Error>> {
+        let root = derive_root(&self.authority, &self.id);
+        Ok(self.fetch(&[root])?.remove(0))
+    }
+
+    // Loads and checks the root and its branches up front, leaves are then
+    // fetched a batch at a time as the iterator advances
+    pub fn elements(&self) -> Result<Elements<'_, F>, ReaderError<F::Error>> {
+        let root_key = derive_root(&self.authority, &self.id);
+        let root = self.root()?;
+
+        // Prepaid lists hold default entries until their branch is allocated
+        let branch_indices =
+            self.linked(root_key, &root, |j| derive_j(&self.authority, &self.id, j))?;
+        let branch_keys: Vec<Pubkey> = branch_indices
+            .iter()
+            .map(|j| root.elements[*j as usize])
+            .collect();
+        let branches = self.fetch(&branch_keys)?;
+
+        let found = branches.iter().map(|branch| branch.total_elements).sum();
+        if found != root.total_elements {
+            return Err(ReaderError::TotalMismatch {
+                node: root_key,
+                expected: root.total_elements,
+                found,
+            });
+        }
+
+        let mut leaves = vec![];
+        for ((j, branch_key), branch) in branch_indices.iter().zip(&branch_keys).zip(&branches) {
+            let leaf_indices = self.linked(*branch_key, branch, |k| {
+                derive_k(&self.authority, &self.id, *j, k)
+            })?;
+            for (i, k) in leaf_indices.iter().enumerate() {
+                leaves.push(PendingLeaf {
+                    branch: *branch_key,
+                    pubkey: branch.elements[*k as usize],
+                    branch_total: (i == leaf_indices.len() - 1).then_some(branch.total_elements),
+                });
+            }
+        }
+
+        Ok(Elements {
+            reader: self,
+            leaves,
+            next_leaf: 0,
+            buffer: vec![].into_iter(),
+            branch_count: 0,
+            failed: false,
+        })
+    }
+
+    // The allocated children of `node`, checking each against its derived address
+    fn linked(
+        &self,
+        node_key: Pubkey,
+        node: &BigList,
+        derive: impl Fn(u8) -> Pubkey,
+    ) -> Result<Vec<u8>, ReaderError<F::Error>> {
+        let mut indices = vec![];
+        for (index, child) in node.elements[..node.len as usize].iter().enumerate() {
+            if *child == Pubkey::default() {
+                continue;
+            }
+            if *child != derive(index as u8) {
+                return Err(ReaderError::BrokenLink {
+                    node: node_key,
+                    index: index as u8,
+                });
+            }
+            indices.push(index as u8);
+        }
+        Ok(indices)
+    }
+
+    fn fetch(&self, pubkeys: &[Pubkey]) -> Result<Vec<BigList>, ReaderError<F::Error>> {
+        let mut nodes = Vec::with_capacity(pubkeys.len());
+        for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let fetched = self
+                .fetcher
+                .get_multiple_accounts(chunk, self.min_context_slot)
+                .map_err(ReaderError::Fetch)?;
+            self.context_slots.set(Some(match self.context_slots.get() {
+                Some((lowest, highest)) => (lowest.min(fetched.slot), highest.max(fetched.slot)),
+                None => (fetched.slot, fetched.slot),
+            }));
+            for (pubkey, data) in chunk.iter().zip(fetched.accounts) {
+                let data = data.ok_or(ReaderError::AccountNotFound(*pubkey))?;
+                let node = BigList::try_deserialize(&mut data.as_slice())
+                    .map_err(|_| ReaderError::InvalidAccount(*pubkey))?;
+                nodes.push(node);
+            }
+        }
+        Ok(nodes)
+    }
+}
+
+struct PendingLeaf {
+    branch: Pubkey,
+    pubkey: Pubkey,
+    // Set on a branch's last leaf, to check the branch total against
+    branch_total: Option<u32>,
+}
+
+// Yields every element of the list, stopping after the first error
+pub struct Elements<'r, F> {
+    reader: &'r BigListReader<F>,
+    leaves: Vec<PendingLeaf>,
+    next_leaf: usize,
+    buffer: std::vec::IntoIter<Pubkey>,
+    // Elements seen so far in the current branch
+    branch_count: u32,
+    failed: bool,
+}
+
+impl<'r, F: AccountFetcher> Elements<'r, F> {
+    fn load_batch(&mut self) -> Result<(), ReaderError<F::Error>> {
+        let end = (self.next_leaf + MAX_MULTIPLE_ACCOUNTS).min(self.leaves.len());
+        let batch = &self.leaves[self.next_leaf..end];
+        let keys: Vec<Pubkey> = batch.iter().map(|leaf| leaf.pubkey).collect();
+        let nodes = self.reader.fetch(&keys)?;
+
+        let mut elements = vec![];
+        for (pending, leaf) in batch.iter().zip(nodes) {
+            // Prepaid leaves are sized to capacity, only `len` are written
+            elements.extend_from_slice(&leaf.elements[..leaf.len as usize]);
+            self.branch_count += leaf.len as u32;
+            if let Some(expected) = pending.branch_total {
+                if self.branch_count != expected {
+                    return Err(ReaderError::TotalMismatch {
+                        node: pending.branch,
+                        expected,
+                        found: self.branch_count,
+                    });
+                }
+                self.branch_count = 0;
+            }
+        }
+        self.next_leaf = end;
+        self.buffer = elements.into_iter();
+        Ok(())
+    }
+}
+
+impl<'r, F: AccountFetcher> Iterator for Elements<'r, F> {
+    type Item = Result<Pubkey, ReaderError<F::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        loop {
+            if let Some(element) = self.buffer.next() {
+                return Some(Ok(element));
+            }
+            if self.next_leaf == self.leaves.len() {
+                return None;
+            }
+            if let Err(err) = self.load_batch() {
+                self.failed = true;
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use anchor_lang::AccountSerialize;
+    use big_list::state::IndexType;
+
+    fn gen_node(depth: u8, index: u8, elements: Vec<Pubkey>, total_elements: u32) -> Vec<u8> {
+        let node = BigList {
+            version: 0,
+            index_type: IndexType::GrowableIndex,
+            authority: Pubkey::default(),
+            len: elements.len() as u16,
+            depth,
+            index,
+            total_elements,
+            capacity: 0,
+            elements,
+            created_at: 0,
+            unique: false,
+            summaries: vec![],
+        };
+        let mut data = vec![];
+        node.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    // A list of `total` elements filling leaves in order
+    pub(crate) fn gen_store(
+        authority: &Pubkey,
+        total: u32,
+    ) -> (HashMap<Pubkey, Vec<u8>>, Vec<Pubkey>) {
+        let id = "my_big_list";
+        let elements: Vec<Pubkey> = (0..total).map(|_| Pubkey::new_unique()).collect();
+        let mut store = HashMap::new();
+        let leaves: Vec<&[Pubkey]> = elements.chunks(256).collect();
+        let branches: Vec<&[&[Pubkey]]> = leaves.chunks(256).collect();
+
+        let mut branch_keys = vec![];
+        for (j, branch) in branches.iter().enumerate() {
+            let mut leaf_keys = vec![];
+            for (k, leaf) in branch.iter().enumerate() {
+                let key = derive_k(authority, id, j as u8, k as u8);
+                store.insert(key, gen_node(2, k as u8, leaf.to_vec(), leaf.len() as u32));
+                leaf_keys.push(key);
+            }
+            let key = derive_j(authority, id, j as u8);
+            let count = branch.iter().map(|leaf| leaf.len() as u32).sum();
+            store.insert(key, gen_node(1, j as u8, leaf_keys, count));
+            branch_keys.push(key);
+        }
+        store.insert(
+            derive_root(authority, id),
+            gen_node(0, 0, branch_keys, total),
+        );
+        (store, elements)
+    }
+
+    #[test]
+    pub fn it_reads_elements_in_order() {
+        let authority = Pubkey::new_unique();
+        // More leaves than one `getMultipleAccounts` call returns
+        let (store, elements) = gen_store(&authority, 256 * 120 + 7);
+        let reader = BigListReader::new(store, authority, "my_big_list");
+
+        let read: Vec<Pubkey> = reader
+            .elements()
+            .unwrap()
+            .map(|element| element.unwrap())
+            .collect();
+        assert_eq!(read, elements);
+    }
+
+    #[test]
+    pub fn it_rejects_inconsistent_totals() {
+        let authority = Pubkey::new_unique();
+        let (mut store, _) = gen_store(&authority, 300);
+        let leaf = derive_k(&authority, "my_big_list", 0, 1);
+        store.insert(leaf, gen_node(2, 1, vec![Pubkey::new_unique()], 1));
+        let reader = BigListReader::new(store, authority, "my_big_list");
+
+        let err = reader.elements().unwrap().find_map(|element| element.err());
+        assert_eq!(
+            err,
+            Some(ReaderError::TotalMismatch {
+                node: derive_j(&authority, "my_big_list", 0),
+                expected: 300,
+                found: 257,
+            })
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use std::fmt;
+
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::hash::{hashv, Hash},
+};
+
+use crate::reader::{AccountFetcher, BigListReader, ReaderError};
+
+const MAGIC: &[u8; 8] = b"BLSNAP01";
+// Reads `capture` makes before giving up on one slot for every account
+pub const CAPTURE_ATTEMPTS: usize = 3;
+const HEADER_LEN: usize = 8 + 32 + 8 + 4;
+
+#[derive(Debug, PartialEq)]
+pub enum SnapshotError {
+    Truncated,
+    BadMagic,
+    // The stored hash doesn't match the elements
+    HashMismatch { expected: Hash, found: Hash },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Truncated => write!(f, "Snapshot is truncated"),
+            SnapshotError::BadMagic => write!(f, "Not a big list snapshot"),
+            SnapshotError::HashMismatch { expected, found } => {
+                write!(
+                    f,
+                    "Snapshot hash is {} but its elements hash to {}",
+                    expected, found
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+// A list's elements as read at `slot`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub list: Pubkey,
+    pub slot: u64,
+    pub elements: Vec<Pubkey>,
+}
+
+impl Snapshot {
+    // Reads every element at one slot, no earlier than `min_slot`. Leaves
+    // take several fetches, so when a fetch lands on a later slot than the
+    // others the read starts over from that slot, `CAPTURE_ATTEMPTS` times
+    // at most.
+    pub fn capture<F: AccountFetcher>(
+        reader: &mut BigListReader<F>,
+        list: Pubkey,
+        min_slot: u64,
+    ) -> Result<Snapshot, ReaderError<F::Error>> {
+        let mut min_slot = min_slot;
+        let mut attempts = 0;
+        loop {
+            reader.set_min_context_slot(Some(min_slot));
+            reader.take_context_slots();
+            let elements = reader.elements()?.collect::<Result<_, _>>()?;
+            let (lowest, highest) = reader
+                .take_context_slots()
+                .expect("The root is always fetched");
+            if lowest == highest {
+                return Ok(Snapshot {
+                    list,
+                    slot: lowest,
+                    elements,
+                });
+            }
+
+            attempts += 1;
+            if attempts == CAPTURE_ATTEMPTS {
+                return Err(ReaderError::SlotMismatch { lowest, highest });
+            }
+            min_slot = highest;
+        }
+    }
+
+    // SHA-256 over the element bytes in index order
+    pub fn hash(&self) -> Hash {
+        let bytes: Vec<&[u8]> = self
+            .elements
+            .iter()
+            .map(|element| element.as_ref())
+            .collect();
+        hashv(&bytes)
+    }
+
+    // Magic, list, slot (u64 LE), count (u32 LE), elements, then the hash
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.elements.len() * 32 + 32);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(self.list.as_ref());
+        bytes.extend_from_slice(&self.slot.to_le_bytes());
+        bytes.extend_from_slice(&(self.elements.len() as u32).to_le_bytes());
+        for element in &self.elements {
+            bytes.extend_from_slice(element.as_ref());
+        }
+        bytes.extend_from_slice(self.hash().as_ref());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Snapshot, SnapshotError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(SnapshotError::Truncated);
+        }
+        if &bytes[..8] != MAGIC {
+            return Err(SnapshotError::BadMagic);
+        }
+        let list = Pubkey::new(&bytes[8..40]);
+        let slot = u64::from_le_bytes(bytes[40..48].try_into().unwrap());
+        let count = u32::from_le_bytes(bytes[48..52].try_into().unwrap()) as usize;
+        if bytes.len() != HEADER_LEN + count * 32 + 32 {
+            return Err(SnapshotError::Truncated);
+        }
+
+        let body = &bytes[HEADER_LEN..HEADER_LEN + count * 32];
+        let snapshot = Snapshot {
+            list,
+            slot,
+            elements: body.chunks(32).map(Pubkey::new).collect(),
+        };
+        let expected = Hash::new(&bytes[HEADER_LEN + count * 32..]);
+        let found = snapshot.hash();
+        if expected != found {
+            return Err(SnapshotError::HashMismatch { expected, found });
+        }
+        Ok(snapshot)
+    }
+
+    // One `index,address` row per element under a header
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("index,address\n");
+        for (index, element) in self.elements.iter().enumerate() {
+            csv.push_str(&format!("{},{}\n", index, element));
+        }
+        csv
+    }
+}
+
+// Indices that differ between two versions of a list
+#[derive(Debug, Default, PartialEq)]
+pub struct Diff {
+    // Past the end of the old list
+    pub added: Vec<u32>,
+    // Past the end of the new list
+    pub removed: Vec<u32>,
+    // Held a different element in each
+    pub changed: Vec<u32>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+pub fn diff(old: &[Pubkey], new: &[Pubkey]) -> Diff {
+    let shared = old.len().min(new.len());
+    Diff {
+        added: (shared..new.len()).map(|index| index as u32).collect(),
+        removed: (shared..old.len()).map(|index| index as u32).collect(),
+        changed: (0..shared)
+            .filter(|index| old[*index] != new[*index])
+            .map(|index| index as u32)
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap, convert::Infallible};
+
+    use super::*;
+    use crate::{pda::derive_root, reader::tests::gen_store, Fetched};
+
+    // Serves `store` at the next of `slots` for every fetch, or at the
+    // minimum asked for when that's later
+    struct SlotFetcher {
+        store: HashMap<Pubkey, Vec<u8>>,
+        slots: RefCell<Vec<u64>>,
+    }
+
+    impl AccountFetcher for SlotFetcher {
+        type Error = Infallible;
+
+        fn get_multiple_accounts(
+            &self,
+            pubkeys: &[Pubkey],
+            min_context_slot: Option<u64>,
+        ) -> Result<Fetched, Infallible> {
+            let slot = self.slots.borrow_mut().remove(0);
+            Ok(Fetched {
+                slot: slot.max(min_context_slot.unwrap_or(0)),
+                accounts: self.store.get_multiple_accounts(pubkeys, None)?.accounts,
+            })
+        }
+    }
+
+    fn gen_reader(slots: Vec<u64>) -> (BigListReader<SlotFetcher>, Vec<Pubkey>, Pubkey) {
+        let authority = Pubkey::new_unique();
+        // Root, branch and leaves, one fetch each
+        let (store, elements) = gen_store(&authority, 300);
+        let fetcher = SlotFetcher {
+            store,
+            slots: RefCell::new(slots),
+        };
+        let reader = BigListReader::new(fetcher, authority, "my_big_list");
+        (reader, elements, derive_root(&authority, "my_big_list"))
+    }
+
+    #[test]
+    pub fn it_captures_at_one_slot() {
+        // The leaves land on a later slot, so the read starts over from it
+        let (mut reader, elements, list) = gen_reader(vec![5, 5, 6, 6, 6, 6]);
+        let snapshot = Snapshot::capture(&mut reader, list, 4).unwrap();
+        assert_eq!(snapshot.slot, 6);
+        assert_eq!(snapshot.elements, elements);
+    }
+
+    #[test]
+    pub fn it_gives_up_when_slots_keep_moving() {
+        let (mut reader, _, list) = gen_reader((1..=9).collect());
+        assert_eq!(
+            Snapshot::capture(&mut reader, list, 0),
+            Err(ReaderError::SlotMismatch {
+                lowest: 7,
+                highest: 9
+            })
+        );
+    }
+
+    #[test]
+    pub fn it_round_trips_snapshots() {
+        let snapshot = Snapshot {
+            list: Pubkey::new_unique(),
+            slot: 42,
+            elements: (0..300).map(|_| Pubkey::new_unique()).collect(),
+        };
+        let mut bytes = snapshot.to_bytes();
+        assert_eq!(Snapshot::from_bytes(&bytes), Ok(snapshot.clone()));
+
+        bytes[HEADER_LEN] ^= 1;
+        assert!(matches!(
+            Snapshot::from_bytes(&bytes),
+            Err(SnapshotError::HashMismatch { .. })
+        ));
+        assert_eq!(
+            Snapshot::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(SnapshotError::Truncated)
+        );
+    }
+
+    #[test]
+    pub fn it_diffs_by_index() {
+        let old: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let mut new = old[..3].to_vec();
+        new[1] = Pubkey::new_unique();
+
+        assert_eq!(
+            diff(&old, &new),
+            Diff {
+                added: vec![],
+                removed: vec![3],
+                changed: vec![1],
+            }
+        );
+        assert_eq!(diff(&new, &old).added, vec![3]);
+        assert!(diff(&old, &old).is_empty());
+    }
+}
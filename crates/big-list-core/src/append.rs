@@ -0,0 +1,47 @@
+use crate::geometry::LEAF_CAPACITY;
+
+// Elements in the leaf `append` writes to next. That is the leaf of the
+// last element, so a full leaf counts as 256 rather than 0.
+pub fn get_leaf_len(total_elements: u32) -> usize {
+    match total_elements as usize % LEAF_CAPACITY {
+        0 if total_elements > 0 => LEAF_CAPACITY,
+        len => len,
+    }
+}
+
+// Whether appending `count` elements spills into a new leaf, which takes
+// `append_rollover_k` rather than `append`
+pub fn needs_rollover(total_elements: u32, count: usize) -> bool {
+    get_leaf_len(total_elements) + count > LEAF_CAPACITY
+}
+
+// How many of `count` elements `append_rollover_k` writes to the current
+// leaf and how many to the next one. None when they don't fill the current
+// leaf or overflow the next.
+pub fn split_rollover(leaf_len: usize, count: usize) -> Option<(usize, usize)> {
+    let remaining = LEAF_CAPACITY.checked_sub(leaf_len)?;
+    let rest = count.checked_sub(remaining)?;
+    if rest > LEAF_CAPACITY {
+        return None;
+    }
+    Some((remaining, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn it_splits_rollovers() {
+        assert_eq!(get_leaf_len(0), 0);
+        assert_eq!(get_leaf_len(256), 256);
+        assert_eq!(get_leaf_len(257), 1);
+        assert!(needs_rollover(250, 7));
+        assert!(!needs_rollover(250, 6));
+
+        assert_eq!(split_rollover(250, 10), Some((6, 4)));
+        assert_eq!(split_rollover(256, 1), Some((0, 1)));
+        assert_eq!(split_rollover(250, 5), None);
+        assert_eq!(split_rollover(0, 513), None);
+    }
+}
@@ -0,0 +1,149 @@
+// Elements per leaf
+pub const LEAF_CAPACITY: usize = 256;
+// Leaves per branch, and branches per root
+pub const BRANCH_CAPACITY: usize = 256;
+pub const MAX_ELEMENTS: u32 = 256 * 256 * 256;
+
+fn assert_total(total: u32) {
+    if total > MAX_ELEMENTS {
+        panic!("total is too large")
+    }
+}
+
+// Branch, leaf and position of the last element, see `get_current_indices`
+pub fn get_j(total: u32) -> u8 {
+    get_current_indices(total).0
+}
+
+pub fn get_k(total: u32) -> u8 {
+    get_current_indices(total).1
+}
+
+pub fn get_l(total: u32) -> u8 {
+    get_current_indices(total).2
+}
+
+// Position of the last element. An empty list has none and reports the
+// same (0, 0, 0) as a list of one.
+pub fn get_current_indices(total_elements: u32) -> (u8, u8, u8) {
+    if total_elements == 0 {
+        return (0, 0, 0);
+    }
+    assert_total(total_elements);
+    let input = total_elements - 1;
+    let j = input / (256 * 256);
+    let k = input % (256 * 256) / 256;
+    let l = input % 256;
+    (j as u8, k as u8, l as u8)
+}
+
+// Inverse of `get_current_indices`, the 1-based total whose last element
+// sits at (j, k, l)
+pub fn get_total(j: u8, k: u8, l: u8) -> u32 {
+    get_index(j, k, l) + 1
+}
+
+// 0-based index of the element at (j, k, l)
+pub fn get_index(j: u8, k: u8, l: u8) -> u32 {
+    get_leaf_offset(j, k) + l as u32
+}
+
+// Index of the first element of leaf `k` in branch `j`
+pub fn get_leaf_offset(j: u8, k: u8) -> u32 {
+    (j as u32) * 256 * 256 + (k as u32) * 256
+}
+
+// Index of the first element in the leaf holding `total`
+pub fn get_leaf_start(total: u32) -> u32 {
+    get_leaf_offset(get_j(total), get_k(total))
+}
+
+// Leaves needed to hold `total_elements`
+pub fn get_leaf_total(total_elements: u32) -> u32 {
+    (total_elements + 255) / 256
+}
+
+// Branches needed to hold `total_elements`
+pub fn get_branch_count(total_elements: u32) -> usize {
+    let leaves = get_leaf_total(total_elements) as usize;
+    (leaves + 255) / 256
+}
+
+// Leaves under branch `j` of a list holding `total_elements`
+pub fn get_leaf_count(total_elements: u32, j: u8) -> usize {
+    let leaves = get_leaf_total(total_elements) as usize;
+    core::cmp::min(leaves.saturating_sub(j as usize * 256), 256)
+}
+
+// Elements held by leaf `k` of branch `j`
+pub fn get_leaf_capacity(total_elements: u32, j: u8, k: u8) -> usize {
+    let start = get_leaf_offset(j, k);
+    core::cmp::min(total_elements.saturating_sub(start), 256) as usize
+}
+
+pub fn is_valid_capacity(capacity: u32) -> bool {
+    capacity > 0 && capacity <= MAX_ELEMENTS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    pub fn it_returns_the_expected_indices() {
+        assert_eq!(get_current_indices(256), (0, 0, 255));
+        assert_eq!(get_current_indices(257), (0, 1, 0));
+        assert_eq!(get_current_indices(255), (0, 0, 254));
+        assert_eq!(get_current_indices(10000), (0, 39, 15));
+        // Max
+        assert_eq!(get_current_indices(16777216), (255, 255, 255));
+    }
+
+    #[test]
+    pub fn it_returns_the_expected_k() {
+        assert_eq!(get_k(256), 0);
+        assert_eq!(get_k(257), 1);
+        assert_eq!(get_k(255), 0);
+        assert_eq!(get_k(10000), 39);
+        // Max
+        assert_eq!(get_k(16777216), 255);
+    }
+
+    #[test]
+    pub fn it_reports_the_empty_list_as_its_first_element() {
+        assert_eq!(get_current_indices(0), get_current_indices(1));
+    }
+
+    #[test]
+    pub fn it_sizes_prepaid_lists() {
+        // 100000 elements fill 391 leaves, 256 in the first branch
+        assert_eq!(get_branch_count(100000), 2);
+        assert_eq!(get_leaf_count(100000, 0), 256);
+        assert_eq!(get_leaf_count(100000, 1), 135);
+        assert_eq!(get_leaf_capacity(100000, 1, 134), 100000 - 390 * 256);
+        assert_eq!(get_leaf_capacity(100000, 1, 133), 256);
+        assert_eq!(get_branch_count(65536), 1);
+        assert_eq!(get_leaf_count(65536, 1), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn it_round_trips_indices(total in 1..=MAX_ELEMENTS) {
+            let (j, k, l) = get_current_indices(total);
+            prop_assert_eq!((get_j(total), get_k(total), get_l(total)), (j, k, l));
+            prop_assert_eq!(get_total(j, k, l), total);
+
+            let start = get_leaf_start(total);
+            prop_assert!(start < total && total <= start + 256);
+            prop_assert_eq!(get_leaf_capacity(total, j, k), (total - start) as usize);
+        }
+
+        #[test]
+        fn it_counts_the_leaves_it_indexes(total in 1..=MAX_ELEMENTS) {
+            let (j, k, _) = get_current_indices(total);
+            prop_assert_eq!(get_branch_count(total), j as usize + 1);
+            prop_assert_eq!(get_leaf_count(total, j), k as usize + 1);
+        }
+    }
+}
@@ -0,0 +1,10 @@
+// Geometry of a big list: a root holds up to 256 branches (J), each branch
+// up to 256 leaves (K) and each leaf up to 256 elements (L). Plain integer
+// math with no dependencies, so the program, clients and tests share it.
+#![cfg_attr(not(test), no_std)]
+
+pub mod append;
+pub mod geometry;
+
+pub use append::*;
+pub use geometry::*;
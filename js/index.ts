@@ -31,6 +31,47 @@ export const getBigList = (
 
 const MAX_LIST_VECTOR_SIZE = 256;
 
+// Child of a sorted list node whose key range holds `key`, mirrors
+// `BigList::find_child`
+const findChild = (
+  summaries: { first: PublicKey; last: PublicKey }[],
+  key: PublicKey
+): number | undefined => {
+  let [low, high] = [0, summaries.length];
+  while (low < high) {
+    const mid = Math.floor((low + high) / 2);
+    if (Buffer.compare(summaries[mid].last.toBuffer(), key.toBuffer()) < 0) {
+      low = mid + 1;
+    } else if (Buffer.compare(summaries[mid].first.toBuffer(), key.toBuffer()) > 0) {
+      high = mid;
+    } else {
+      return mid;
+    }
+  }
+  return undefined;
+};
+
+// The [j, k] leaf to pass to `find` for `key`, undefined when it is absent
+export const findLeaf = async (
+  listId: string,
+  key: PublicKey,
+  program: Program<BigList>
+): Promise<[number, number] | undefined> => {
+  const authority = program.provider.publicKey;
+  const bigList = await program.account.bigList.fetch(
+    getBigList(authority, listId)
+  );
+  const j = findChild(bigList.summaries, key);
+  if (j === undefined) {
+    return undefined;
+  }
+  const bigListJ = await program.account.bigList.fetch(
+    getBigList(authority, listId, j)
+  );
+  const k = findChild(bigListJ.summaries, key);
+  return k === undefined ? undefined : [j, k];
+};
+
 export const getCurrentIndices = (
   totalElements: number
 ): [number, number, number] => {
@@ -185,12 +226,73 @@ export const getClockworkThreadPDA = async (
 
 export const getBatchProccessPDA = async (
   authority: PublicKey,
-  id: string
+  id: string,
+  batchId: BN
 ): Promise<PublicKey> => {
   const [pubkey] = await PublicKey.findProgramAddress(
-    [Buffer.from("batch_process"), Buffer.from(id), authority.toBuffer()],
+    [
+      Buffer.from("batch_process"),
+      Buffer.from(id),
+      authority.toBuffer(),
+      batchId.toArrayLike(Buffer, "le", 8),
+    ],
+    BIG_LIST_PROGRAM_ID
+  );
+  return pubkey;
+};
+
+
+export const getDistributionReceiptsPDA = (
+  batchProcess: PublicKey,
+  j: number,
+  k: number
+): PublicKey => {
+  const [pubkey] = PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("receipts"),
+      batchProcess.toBuffer(),
+      Buffer.from(k.toString()),
+      Buffer.from(j.toString()),
+    ],
     BIG_LIST_PROGRAM_ID
   );
   return pubkey;
 };
 
+export const getVaultPDA = (batchProcess: PublicKey): PublicKey => {
+  const [pubkey] = PublicKey.findProgramAddressSync(
+    [Buffer.from("vault"), batchProcess.toBuffer()],
+    BIG_LIST_PROGRAM_ID
+  );
+  return pubkey;
+};
+
+export const getBatchShardPDA = (
+  batchProcess: PublicKey,
+  index: number
+): PublicKey => {
+  const [pubkey] = PublicKey.findProgramAddressSync(
+    [Buffer.from("shard"), batchProcess.toBuffer(), Buffer.from([index])],
+    BIG_LIST_PROGRAM_ID
+  );
+  return pubkey;
+};
+
+export const getSetBucketPDA = (
+  bigList: PublicKey,
+  prefix: number
+): PublicKey => {
+  const [pubkey] = PublicKey.findProgramAddressSync(
+    [Buffer.from("bucket"), bigList.toBuffer(), Buffer.from([prefix])],
+    BIG_LIST_PROGRAM_ID
+  );
+  return pubkey;
+};
+
+export const getBatchFailuresPDA = (batchProcess: PublicKey): PublicKey => {
+  const [pubkey] = PublicKey.findProgramAddressSync(
+    [Buffer.from("failures"), batchProcess.toBuffer()],
+    BIG_LIST_PROGRAM_ID
+  );
+  return pubkey;
+};
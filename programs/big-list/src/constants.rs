@@ -1 +1,13 @@
-pub const MAX_LIST_VECTOR_SIZE: usize = 256;
\ No newline at end of file
+pub const MAX_LIST_VECTOR_SIZE: usize = big_list_core::LEAF_CAPACITY;
+
+// A legacy transaction fits 33 account keys once the signature, header,
+// blockhash and the `thread_exec` instruction itself are paid for.
+pub const MAX_TRANSACTION_ACCOUNTS: usize = 33;
+// Keys `thread_exec` adds around the thread's instruction: fee, penalty,
+// pool, signatory, worker and this program.
+pub const THREAD_EXEC_ACCOUNTS: usize = 6;
+// Fixed accounts of `batch_distribute`, before the recipients. Its payer is
+// the worker's signatory, which `thread_exec` already carries.
+pub const BATCH_DISTRIBUTE_ACCOUNTS: usize = 11;
+pub const MAX_BATCH_SIZE: usize =
+    MAX_TRANSACTION_ACCOUNTS - THREAD_EXEC_ACCOUNTS - BATCH_DISTRIBUTE_ACCOUNTS;
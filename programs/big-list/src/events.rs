@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+
+use crate::state::DistributionMode;
+
+#[event]
+pub struct ListInitialized {
+    pub big_list: Pubkey,
+    pub authority: Pubkey,
+    pub id: String,
+}
+
+// `count` elements written from `start_index` into the K leaf at `leaf`
+#[event]
+pub struct ElementsAppended {
+    pub big_list: Pubkey,
+    pub start_index: u32,
+    pub count: u32,
+    pub leaf: [u8; 2],
+}
+
+// `count` elements of a prepaid list overwritten from `start_index`
+#[event]
+pub struct ElementsWritten {
+    pub big_list: Pubkey,
+    pub start_index: u32,
+    pub count: u32,
+    pub leaf: [u8; 2],
+}
+
+// A full K leaf was closed and `big_list_k` opened at `leaf`
+#[event]
+pub struct LeafRolledOver {
+    pub big_list: Pubkey,
+    pub leaf: [u8; 2],
+    pub big_list_k: Pubkey,
+}
+
+#[event]
+pub struct BatchStarted {
+    pub batch_process: Pubkey,
+    pub big_list: Pubkey,
+    pub batch_id: u64,
+    pub mode: DistributionMode,
+    pub amount: u64,
+    pub total_elements: u32,
+}
+
+// One step over the elements in `start_index..start_index + count`
+#[event]
+pub struct BatchStepProcessed {
+    pub batch_process: Pubkey,
+    pub shard: Option<u8>,
+    pub start_index: u32,
+    pub count: u32,
+    pub total_processed: u32,
+}
+
+#[event]
+pub struct BatchCompleted {
+    pub batch_process: Pubkey,
+    pub total_processed: u32,
+}
+
+// What a simulated batch step would have done for one recipient
+#[event]
+pub struct DistributionSimulated {
+    pub batch_process: Pubkey,
+    pub recipient: Pubkey,
+    pub index: u32,
+    pub amount: u64,
+    // False when the transfer would be logged for `retry_failed` instead
+    pub payable: bool,
+}
@@ -0,0 +1,121 @@
+use anchor_lang::{prelude::*, solana_program};
+
+use crate::{
+    state::{BigList, IndexType},
+    utils::{get_leaf_capacity, get_leaf_count},
+};
+
+#[derive(Accounts)]
+#[instruction(id: String, j: u8)]
+pub struct AllocateLeaves<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = big_list.index_type == IndexType::PrepaidIndex,
+        seeds = [id.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub big_list: Account<'info, BigList>,
+    // A full branch with its summaries would not fit in one allocation, so
+    // `sync_totals` grows them as it goes
+    #[account(
+        init_if_needed,
+        seeds = [j.to_string().as_ref(), id.as_ref(), authority.key().as_ref()],
+        bump,
+        space = BigList::size(get_leaf_count(big_list.capacity, j)),
+        payer = authority,
+    )]
+    pub big_list_j: Account<'info, BigList>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Remaining accounts are the leaves `k_start..k_start + count` of branch `j`,
+// created at their full size.
+pub fn process<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, AllocateLeaves<'info>>,
+    id: String,
+    j: u8,
+    k_start: u8,
+    count: u8,
+) -> Result<()> {
+    let big_list = &mut ctx.accounts.big_list;
+    let big_list_j = &mut ctx.accounts.big_list_j;
+    let authority = &ctx.accounts.authority;
+    let now = Clock::get().unwrap().unix_timestamp;
+
+    if j as usize >= big_list.elements.len() {
+        panic!("Branch {} is past the capacity of the list", j)
+    }
+    let leaves = get_leaf_count(big_list.capacity, j);
+    if k_start as usize + count as usize > leaves {
+        panic!("Branch {} only holds {} leaves", j, leaves)
+    }
+    if ctx.remaining_accounts.len() != count as usize {
+        panic!("Expected {} leaves", count)
+    }
+
+    if big_list.elements[j as usize] == Pubkey::default() {
+        big_list_j.init(1, j, authority.key(), now, None);
+        big_list_j.index_type = IndexType::PrepaidIndex;
+        big_list_j.capacity = (0..leaves as u8)
+            .map(|k| get_leaf_capacity(big_list.capacity, j, k) as u32)
+            .sum();
+        big_list_j.elements = vec![Pubkey::default(); leaves];
+        big_list_j.len = leaves as u16;
+        big_list.elements[j as usize] = big_list_j.key();
+    }
+
+    for (k, leaf) in (k_start..).zip(ctx.remaining_accounts) {
+        let capacity = get_leaf_capacity(big_list.capacity, j, k);
+        let (k_seed, j_seed) = (k.to_string(), j.to_string());
+        let seeds: &[&[u8]] = &[
+            k_seed.as_ref(),
+            j_seed.as_ref(),
+            id.as_ref(),
+            authority.key.as_ref(),
+        ];
+        let (address, bump) = Pubkey::find_program_address(seeds, &crate::ID);
+        if leaf.key() != address {
+            panic!("Expected leaf {},{} at {}", j, k, address)
+        }
+
+        let space = BigList::size(capacity);
+        solana_program::program::invoke_signed(
+            &solana_program::system_instruction::create_account(
+                authority.key,
+                leaf.key,
+                Rent::get()?.minimum_balance(space),
+                space as u64,
+                &crate::ID,
+            ),
+            &[
+                authority.to_account_info(),
+                leaf.clone(),
+                ctx.accounts.system_program.to_account_info(),
+            ],
+            &[&[seeds, &[&[bump]]].concat()],
+        )?;
+
+        let big_list_k = BigList {
+            version: 0,
+            index_type: IndexType::PrepaidIndex,
+            authority: authority.key(),
+            len: 0,
+            depth: 2,
+            index: k,
+            total_elements: 0,
+            capacity: capacity as u32,
+            elements: vec![Pubkey::default(); capacity],
+            created_at: now,
+            unique: false,
+            summaries: vec![],
+        };
+        big_list_k.try_serialize(&mut &mut leaf.try_borrow_mut_data()?[..])?;
+
+        big_list_j.elements[k as usize] = leaf.key();
+    }
+
+    Ok(())
+}
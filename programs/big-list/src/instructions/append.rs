@@ -1,7 +1,8 @@
 use crate::{
     constants::MAX_LIST_VECTOR_SIZE,
-    state::BigList,
-    utils::{get_j, get_k},
+    events::ElementsAppended,
+    state::{BigList, IndexType},
+    utils::{assert_sorted_after, get_j, get_k, insert_into_buckets, update_summaries},
 };
 use anchor_lang::prelude::*;
 
@@ -11,6 +12,7 @@ pub struct Append<'info> {
     #[account(
         has_one = authority,
         mut,
+        constraint = big_list.index_type != IndexType::PrepaidIndex,
         seeds = [id.as_ref(), authority.key().as_ref()],
         bump,
     )]
@@ -41,17 +43,26 @@ pub fn assert_list_does_not_exced_max_len(big_list: &BigList) {
     }
 }
 
-pub fn process(ctx: Context<Append>, _id: String, addresses: Vec<Pubkey>) -> Result<()> {
+// Unique lists pass the buckets of the appended elements as remaining accounts
+pub fn process<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, Append<'info>>,
+    _id: String,
+    addresses: Vec<Pubkey>,
+) -> Result<()> {
     let big_list = &mut ctx.accounts.big_list;
     let big_list_j = &mut ctx.accounts.big_list_j;
     let big_list_k = &mut ctx.accounts.big_list_k;
 
     let addresses_len = addresses.len();
 
-    let next_size = big_list_k.len + (addresses_len as u16);
-    if next_size > 256 {
+    let start_index = big_list.total_elements;
+    if big_list.index_type == IndexType::SortedIndex {
+        assert_sorted_after(big_list_k.elements.last().cloned(), &addresses);
+    }
+    if big_list_k.len as usize + addresses_len > MAX_LIST_VECTOR_SIZE {
         panic!("Going over 256")
     }
+    let next_size = big_list_k.len + (addresses_len as u16);
 
     big_list_k.len = next_size;
     let new_size = (big_list_k.len) as usize;
@@ -63,5 +74,25 @@ pub fn process(ctx: Context<Append>, _id: String, addresses: Vec<Pubkey>) -> Res
     assert_list_does_not_exced_max_len(&big_list_k);
     big_list_k.elements.append(&mut addresses.clone());
     big_list_k.elements.resize(new_size, Pubkey::default());
+
+    let (j, k) = (get_j(start_index), get_k(start_index));
+    update_summaries(big_list, big_list_j, j, k, big_list_k);
+
+    if big_list.unique {
+        insert_into_buckets(
+            big_list,
+            &addresses,
+            ctx.remaining_accounts,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+    }
+
+    emit!(ElementsAppended {
+        big_list: big_list.key(),
+        start_index,
+        count: addresses_len as u32,
+        leaf: [get_j(start_index), get_k(start_index)],
+    });
     Ok(())
 }
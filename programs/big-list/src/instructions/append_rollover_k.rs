@@ -1,7 +1,10 @@
 use crate::{
     constants::MAX_LIST_VECTOR_SIZE,
-    state::BigList,
-    utils::{get_j, get_k},
+    events::{ElementsAppended, LeafRolledOver},
+    state::{BigList, IndexType},
+    utils::{
+        assert_sorted_after, get_j, get_k, insert_into_buckets, split_rollover, update_summaries,
+    },
 };
 use anchor_lang::prelude::*;
 
@@ -11,6 +14,9 @@ pub struct AppendRolloverK<'info> {
     #[account(
         has_one = authority,
         mut,
+        constraint = big_list.index_type != IndexType::PrepaidIndex,
+        // Leaves only roll over within a branch, there's no rollover into the next one
+        constraint = get_j(big_list.total_elements + addresses.len() as u32) == get_j(big_list.total_elements),
         seeds = [id.as_ref(), authority.key().as_ref()],
         bump,
     )]
@@ -19,7 +25,7 @@ pub struct AppendRolloverK<'info> {
         mut,
         seeds = [get_j(big_list.total_elements as u32).to_string().as_ref(), id.as_ref(), authority.key().as_ref()],
         bump,
-        realloc = BigList::size((big_list_j.len + 1) as usize),
+        realloc = BigList::branch_size((big_list_j.len + 1) as usize),
         realloc::payer = authority,
         realloc::zero = false,
     )]
@@ -39,8 +45,8 @@ pub struct AppendRolloverK<'info> {
         seeds = [get_k(((big_list.total_elements as u32) + (addresses.len() as u32)) as u32).to_string().as_ref(), get_j(((big_list.total_elements as u32) + (addresses.len() as u32)) as u32).to_string().as_ref(), id.as_ref(), authority.key().as_ref()],
         bump,
         space = {
-            let remaining_space = ((256 as usize) - (big_list_k.len as usize)) as usize;
-            let rollover_space = addresses.len() - remaining_space;
+            let (_, rollover_space) = split_rollover(big_list_k.len as usize, addresses.len())
+                .expect("Addresses do not roll over into one leaf");
             BigList::size(rollover_space)
         },
         payer = authority,
@@ -59,23 +65,34 @@ pub fn assert_list_does_not_exced_max_len(big_list: &BigList) {
     }
 }
 
-pub fn process(ctx: Context<AppendRolloverK>, _id: String, addresses: Vec<Pubkey>) -> Result<()> {
+// Unique lists pass the buckets of the appended elements as remaining accounts
+pub fn process<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, AppendRolloverK<'info>>,
+    _id: String,
+    addresses: Vec<Pubkey>,
+) -> Result<()> {
     let big_list = &mut ctx.accounts.big_list;
     let big_list_j = &mut ctx.accounts.big_list_j;
     let big_list_k = &mut ctx.accounts.big_list_k;
     let big_list_k_next = &mut ctx.accounts.big_list_k_next;
 
-    let remaining_k_space = (((256 as u16) - &big_list_k.len) as u16) as usize;
+    let (remaining_k_space, _) = split_rollover(big_list_k.len as usize, addresses.len())
+        .expect("Addresses do not roll over into one leaf");
 
     let mut addresses_to_append = addresses.clone();
 
     let (k_address, k_next_addresses) = addresses_to_append.split_at_mut(remaining_k_space);
 
+    let start_index = big_list.total_elements;
+    if big_list.index_type == IndexType::SortedIndex {
+        assert_sorted_after(big_list_k.elements.last().cloned(), &addresses);
+    }
     big_list.total_elements += addresses.len() as u32;
 
     big_list_k.total_elements += k_address.len() as u32;
     big_list_k.len += k_address.len() as u16;
 
+    big_list_k_next.index_type = big_list.index_type;
     big_list_k_next.total_elements += k_next_addresses.len() as u32;
     big_list_k_next.len += k_next_addresses.len() as u16;
 
@@ -91,5 +108,40 @@ pub fn process(ctx: Context<AppendRolloverK>, _id: String, addresses: Vec<Pubkey
     big_list_j.len += 1;
     big_list_j.total_elements += addresses.len() as u32;
 
+    if big_list.unique {
+        insert_into_buckets(
+            big_list,
+            &addresses,
+            ctx.remaining_accounts,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+    }
+
+    let next_leaf = [get_j(big_list.total_elements), get_k(big_list.total_elements)];
+    let (j, k) = (get_j(start_index), get_k(start_index));
+    update_summaries(big_list, big_list_j, j, k, big_list_k);
+    update_summaries(big_list, big_list_j, next_leaf[0], next_leaf[1], big_list_k_next);
+    // A full leaf rolls over without taking any of the elements
+    if remaining_k_space > 0 {
+        emit!(ElementsAppended {
+            big_list: big_list.key(),
+            start_index,
+            count: remaining_k_space as u32,
+            leaf: [get_j(start_index), get_k(start_index)],
+        });
+    }
+    emit!(LeafRolledOver {
+        big_list: big_list.key(),
+        leaf: next_leaf,
+        big_list_k: big_list_k_next.key(),
+    });
+    emit!(ElementsAppended {
+        big_list: big_list.key(),
+        start_index: big_list.total_elements - big_list_k_next.len as u32,
+        count: big_list_k_next.len as u32,
+        leaf: next_leaf,
+    });
+
     Ok(())
 }
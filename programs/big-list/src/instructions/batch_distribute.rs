@@ -1,115 +1,253 @@
 use anchor_lang::{
     prelude::*,
-    solana_program::{self, instruction::Instruction, system_program},
-};
-use clockwork_sdk::{
-    self,
-    state::{Thread, ThreadResponse, Trigger},
-    ThreadProgram,
+    solana_program::{instruction::Instruction, system_program},
 };
 
+#[cfg(feature = "clockwork")]
+use crate::{
+    state::BatchProcessStatus,
+    utils::{advance_batch_process, distribute_batch},
+};
 use crate::{
-    state::{BatchProcess, BatchProcessStatus, BigList},
-    utils::{get_current_indices, get_j, get_k},
+    state::{
+        BatchFailures, BatchProcess, BatchTrigger, BigList, DistributionMode, DistributionReceipts,
+    },
+    thread::{self, anchor_sighash, Thread, ThreadProgram, ThreadResponse, PAYER_PUBKEY},
+    utils::{get_j, get_k, get_leaf_start},
 };
 
 #[derive(Accounts)]
-// #[instruction(id: String)]
 pub struct BatchDistribute<'info> {
-    #[account(seeds = [b"batch_process".as_ref(), b"my_big_list".as_ref(), authority.key().as_ref()], bump)]
-    pub batch_process: Account<'info, BatchProcess>,
     #[account(
+        mut,
         has_one = authority,
-        seeds = [b"my_big_list".as_ref(), authority.key().as_ref()],
+        has_one = big_list,
+        constraint = batch_process.mode == DistributionMode::Push,
+        constraint = !matches!(batch_process.trigger, BatchTrigger::Crank { .. }),
+        constraint = batch_process.shards == 0,
+        seeds = [b"batch_process".as_ref(), batch_process.id.as_ref(), authority.key().as_ref(), batch_process.batch_id.to_le_bytes().as_ref()],
         bump,
     )]
+    pub batch_process: Account<'info, BatchProcess>,
     pub big_list: Account<'info, BigList>,
     #[account(
-        seeds = [get_j(0).to_string().as_ref(), b"my_big_list".as_ref(), authority.key().as_ref()],
+        seeds = [get_j(batch_process.cursor()).to_string().as_ref(), batch_process.id.as_ref(), authority.key().as_ref()],
         bump,
     )]
     pub big_list_j: Account<'info, BigList>,
     #[account(
-        seeds = [get_k(0).to_string().as_ref(), get_j(0).to_string().as_ref(), b"my_big_list".as_ref(), authority.key().as_ref()],
+        seeds = [get_k(batch_process.cursor()).to_string().as_ref(), get_j(batch_process.cursor()).to_string().as_ref(), batch_process.id.as_ref(), authority.key().as_ref()],
         bump,
     )]
     pub big_list_k: Account<'info, BigList>,
-    // #[account(mut)]
+    #[account(
+        init_if_needed,
+        seeds = [b"receipts".as_ref(), batch_process.key().as_ref(), get_k(batch_process.cursor()).to_string().as_ref(), get_j(batch_process.cursor()).to_string().as_ref()],
+        bump,
+        space = DistributionReceipts::LEN,
+        payer = payer,
+    )]
+    pub receipts: Account<'info, DistributionReceipts>,
+    #[account(mut, has_one = batch_process, seeds = [b"failures".as_ref(), batch_process.key().as_ref()], bump)]
+    pub failures: Account<'info, BatchFailures>,
+    #[account(mut, seeds = [b"vault".as_ref(), batch_process.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+    // Clockwork swaps in the worker's signatory and reimburses it from the thread
+    #[account(mut)]
+    pub payer: Signer<'info>,
     /// CHECK: can be anyone
     pub authority: AccountInfo<'info>,
-    #[account(mut, address = Thread::pubkey(batch_process.key(), "my_big_list".to_string()))]
-    pub batch_processor_thread: Account<'info, Thread>,
+    #[account(mut, address = Thread::pubkey(batch_process.key(), batch_process.thread_id()))]
+    pub batch_processor_thread: Signer<'info>,
     pub thread_program: Program<'info, ThreadProgram>,
     #[account(address = system_program::ID)]
     pub system_program: Program<'info, System>,
 }
 
+pub fn batch_distribute_ix(
+    batch_process_key: Pubkey,
+    batch_process: &BatchProcess,
+    big_list_j: Pubkey,
+    big_list_k: Pubkey,
+    receipts: Pubkey,
+    batch_processor_thread: Pubkey,
+    recipients: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(batch_process_key, false),
+        AccountMeta::new_readonly(batch_process.big_list, false),
+        AccountMeta::new_readonly(big_list_j, false),
+        AccountMeta::new_readonly(big_list_k, false),
+        AccountMeta::new(receipts, false),
+        AccountMeta::new(BatchFailures::pubkey(batch_process_key), false),
+        AccountMeta::new(BatchProcess::vault(batch_process_key), false),
+        AccountMeta::new(PAYER_PUBKEY, true),
+        AccountMeta::new_readonly(batch_process.authority, false),
+        AccountMeta::new(batch_processor_thread, true),
+        AccountMeta::new_readonly(thread::ID, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+
+    for recipient in recipients {
+        accounts.push(AccountMeta::new(*recipient, false));
+    }
+    accounts.extend(batch_process.action.extra_accounts());
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data: anchor_sighash("batch_distribute").into(),
+    }
+}
 
+#[cfg(feature = "clockwork")]
 pub fn process<'a, 'b, 'c, 'info>(
     ctx: Context<'a, 'b, 'c, 'info, BatchDistribute<'info>>,
-) -> Result<(ThreadResponse)> {
+) -> Result<ThreadResponse> {
     let batch_process = &mut ctx.accounts.batch_process;
-    let big_list = &ctx.accounts.big_list;
     let big_list_j = &ctx.accounts.big_list_j;
     let big_list_k = &ctx.accounts.big_list_k;
-    let thread_program = &ctx.accounts.thread_program;
-    let system_program = &ctx.accounts.system_program;
+    let receipts = &mut ctx.accounts.receipts;
     let batch_processor_thread = &ctx.accounts.batch_processor_thread;
-    let authority = &ctx.accounts.authority;
-
-
-    batch_process.status = BatchProcessStatus::Processing;
-
-    let next_total = batch_process.total_processed + ctx.remaining_accounts.len() as u32;
-    let next_position = get_current_indices(next_total);
-    batch_process.total_processed = next_total;
-    batch_process.position = [next_position.0, next_position.1, next_position.2];
-
-    msg!("Batch Total: {}", batch_process.total_processed);
-
-    for account_info in ctx.remaining_accounts {
-        // Add 5 SOL to thread to cover fees
-        solana_program::program::invoke(
-            &solana_program::system_instruction::transfer(
-                &ctx.accounts.batch_processor_thread.key(),
-                &account_info.key(),
-                100_000,
-            ),
-            &[
-                ctx.accounts.batch_processor_thread.to_account_info(),
-                account_info.clone(),
-                ctx.accounts.system_program.to_account_info(),
-            ],
-        )?;
-
-        msg!("Payed {} 100,000 Lamports", account_info.key());
+
+    if batch_process.status == BatchProcessStatus::Completed {
+        return Ok(ThreadResponse::default());
     }
 
-    let mut accounts = vec![
-        AccountMeta::new(batch_process.key(), false),
-        AccountMeta::new_readonly(big_list.key(), false),
-        AccountMeta::new_readonly(big_list_j.key(), false),
-        AccountMeta::new_readonly(big_list_k.key(), false),
-        AccountMeta::new_readonly(authority.key(), false),
-        AccountMeta::new(batch_processor_thread.key(), true),
-        AccountMeta::new_readonly(thread_program.key(), false),
-        AccountMeta::new_readonly(system_program.key(), false),
-    ];
-    
-    for i in batch_process.total_processed..next_total {
-        let pubkey = big_list_k.elements[i as usize];
-        let meta =  AccountMeta::new(pubkey, false);
-        accounts.push(meta);
+    // The thread wakes up every slot, only step once the interval has passed
+    let slot = Clock::get().unwrap().slot;
+    if let BatchTrigger::SlotInterval { interval } = batch_process.trigger {
+        if slot < batch_process.last_step_slot + interval {
+            return Ok(ThreadResponse::default());
+        }
     }
 
-    let create_batch_distribution_ix = Instruction {
-        program_id: crate::ID,
-        accounts: accounts,
-        data: clockwork_sdk::utils::anchor_sighash("batch_distribute").into(),
-    };
-
-    Ok(ThreadResponse {
-        kickoff_instruction: None,
-        next_instruction: Some(create_batch_distribution_ix.into())
-    })
+    let cursor = batch_process.cursor();
+
+    let count = distribute_batch(
+        batch_process,
+        *ctx.bumps.get("batch_process").unwrap(),
+        None,
+        big_list_k,
+        receipts,
+        &mut ctx.accounts.failures,
+        &ctx.accounts.vault.to_account_info(),
+        *ctx.bumps.get("vault").unwrap(),
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        ctx.remaining_accounts,
+    )?;
+    advance_batch_process(batch_process, count);
+
+    if batch_process.status == BatchProcessStatus::Completed {
+        return Ok(ThreadResponse::default());
+    }
+
+    let next_step = NextStep::new(
+        batch_process,
+        big_list_j,
+        big_list_k,
+        receipts.key(),
+        cursor,
+        batch_process.total_processed,
+        batch_process.total_elements,
+    );
+
+    let next_batch_distribution_ix = batch_distribute_ix(
+        batch_process.key(),
+        batch_process,
+        next_step.big_list_j,
+        next_step.big_list_k,
+        next_step.receipts,
+        batch_processor_thread.key(),
+        &next_step.recipients,
+    );
+
+    Ok(next_step_response(&batch_process.trigger, next_batch_distribution_ix))
+}
+
+// Accounts of the step following a cursor
+pub struct NextStep {
+    pub big_list_j: Pubkey,
+    pub big_list_k: Pubkey,
+    pub receipts: Pubkey,
+    pub recipients: Vec<Pubkey>,
+}
+
+impl NextStep {
+    // Steps never span leaves, once this leaf is exhausted the next step
+    // only loads the next leaf so the one after can read its elements.
+    pub fn new(
+        batch_process: &Account<BatchProcess>,
+        big_list_j: &Account<BigList>,
+        big_list_k: &Account<BigList>,
+        receipts: Pubkey,
+        cursor: u32,
+        processed: u32,
+        end: u32,
+    ) -> NextStep {
+        let next_cursor = std::cmp::min(processed + 1, end);
+        let (next_j, next_k) = (get_j(next_cursor), get_k(next_cursor));
+        let same_leaf = (next_j, next_k) == (get_j(cursor), get_k(cursor));
+
+        let next_big_list_j = if next_j == get_j(cursor) {
+            big_list_j.key()
+        } else {
+            BigList::pubkey(batch_process.authority, &batch_process.id, &[next_j])
+        };
+
+        if same_leaf {
+            let offset = (processed - get_leaf_start(next_cursor)) as usize;
+            // Elements appended after the run started are not part of it
+            let len = std::cmp::min(batch_process.batch_size as u32, end - processed);
+            let recipients: Vec<Pubkey> = big_list_k
+                .elements
+                .iter()
+                .skip(offset)
+                .take(len as usize)
+                .cloned()
+                .collect();
+            NextStep {
+                big_list_j: next_big_list_j,
+                big_list_k: big_list_k.key(),
+                receipts,
+                recipients,
+            }
+        } else {
+            NextStep {
+                big_list_j: next_big_list_j,
+                big_list_k: BigList::pubkey(
+                    batch_process.authority,
+                    &batch_process.id,
+                    &[next_j, next_k],
+                ),
+                receipts: DistributionReceipts::pubkey(batch_process.key(), next_j, next_k),
+                recipients: vec![],
+            }
+        }
+    }
+}
+
+#[cfg(feature = "clockwork")]
+pub fn next_step_response(trigger: &BatchTrigger, next_step_ix: Instruction) -> ThreadResponse {
+    match trigger {
+        BatchTrigger::Immediate => ThreadResponse {
+            kickoff_instruction: None,
+            next_instruction: Some(next_step_ix.into()),
+        },
+        // Scheduled runs do one step per trigger, so the next step becomes
+        // the kickoff instead of being chained.
+        BatchTrigger::Cron { .. } | BatchTrigger::SlotInterval { .. } => ThreadResponse {
+            kickoff_instruction: Some(next_step_ix.into()),
+            next_instruction: None,
+        },
+        BatchTrigger::Crank { .. } => unreachable!(),
+    }
+}
+
+#[cfg(not(feature = "clockwork"))]
+pub fn process<'a, 'b, 'c, 'info>(
+    _ctx: Context<'a, 'b, 'c, 'info, BatchDistribute<'info>>,
+) -> Result<ThreadResponse> {
+    thread::unsupported()
 }
@@ -0,0 +1,109 @@
+use anchor_lang::{prelude::*, solana_program::system_program};
+
+use crate::{
+    events::BatchStepProcessed,
+    state::{BatchProcess, BigList, DistributionMode, DistributionReceipts},
+    utils::{get_j, get_k, get_leaf_start, transfer_from_vault, transfer_tokens_from_vault},
+};
+
+#[derive(Accounts)]
+#[instruction(index: u32)]
+pub struct Claim<'info> {
+    #[account(
+        mut,
+        has_one = big_list,
+        constraint = batch_process.mode == DistributionMode::Claim,
+        seeds = [b"batch_process".as_ref(), batch_process.id.as_ref(), batch_process.authority.as_ref(), batch_process.batch_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub batch_process: Account<'info, BatchProcess>,
+    pub big_list: Account<'info, BigList>,
+    #[account(
+        seeds = [get_j(index + 1).to_string().as_ref(), batch_process.id.as_ref(), batch_process.authority.as_ref()],
+        bump,
+    )]
+    pub big_list_j: Account<'info, BigList>,
+    #[account(
+        seeds = [get_k(index + 1).to_string().as_ref(), get_j(index + 1).to_string().as_ref(), batch_process.id.as_ref(), batch_process.authority.as_ref()],
+        bump,
+    )]
+    pub big_list_k: Account<'info, BigList>,
+    #[account(
+        init_if_needed,
+        seeds = [b"receipts".as_ref(), batch_process.key().as_ref(), get_k(index + 1).to_string().as_ref(), get_j(index + 1).to_string().as_ref()],
+        bump,
+        space = DistributionReceipts::LEN,
+        payer = claimant,
+    )]
+    pub receipts: Account<'info, DistributionReceipts>,
+    #[account(mut, seeds = [b"vault".as_ref(), batch_process.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+// Token claims pass [vault token account, claimant token account, token
+// program] as remaining accounts. Each claim counts as a processed element.
+pub fn process<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, Claim<'info>>,
+    index: u32,
+) -> Result<()> {
+    let batch_process = &mut ctx.accounts.batch_process;
+    let big_list_k = &ctx.accounts.big_list_k;
+    let receipts = &mut ctx.accounts.receipts;
+    let claimant = &ctx.accounts.claimant;
+
+    if Clock::get().unwrap().unix_timestamp >= batch_process.expires_at {
+        panic!("Claims expired at {}", batch_process.expires_at)
+    }
+    // Elements appended after the claims opened were never funded
+    if index >= batch_process.total_elements {
+        panic!("Index {} is past the end of the claims", index)
+    }
+
+    let offset = (index - get_leaf_start(index + 1)) as usize;
+    if big_list_k.elements[offset] != claimant.key() {
+        panic!("{} is not at index {}", claimant.key(), index)
+    }
+
+    if receipts.batch_process == Pubkey::default() {
+        receipts.batch_process = batch_process.key();
+        receipts.leaf = [get_j(index + 1), get_k(index + 1)];
+    }
+    receipts.mark_paid(offset);
+
+    match batch_process.mint {
+        None => transfer_from_vault(
+            &ctx.accounts.vault.to_account_info(),
+            &claimant.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            batch_process.key(),
+            *ctx.bumps.get("vault").unwrap(),
+            batch_process.amount,
+        )?,
+        Some(_) if ctx.remaining_accounts.len() < 3 => {
+            panic!("Token claims need the vault token account, the claimant token account and the token program")
+        }
+        Some(_) => transfer_tokens_from_vault(
+            batch_process,
+            *ctx.bumps.get("batch_process").unwrap(),
+            &ctx.remaining_accounts[0],
+            &ctx.remaining_accounts[1],
+            &ctx.remaining_accounts[2],
+            batch_process.amount,
+        )?,
+    }
+
+    batch_process.total_processed += 1;
+    emit!(BatchStepProcessed {
+        batch_process: batch_process.key(),
+        shard: None,
+        start_index: index,
+        count: 1,
+        total_processed: batch_process.total_processed,
+    });
+    msg!("{} claimed {}", claimant.key(), batch_process.amount);
+    Ok(())
+}
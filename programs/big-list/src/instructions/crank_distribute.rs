@@ -0,0 +1,116 @@
+use anchor_lang::{prelude::*, solana_program::system_program};
+
+use crate::{
+    state::{
+        BatchFailures, BatchProcess, BatchProcessStatus, BatchTrigger, BigList, DistributionMode,
+        DistributionReceipts,
+    },
+    utils::{
+        advance_batch_process, distribute_batch, get_j, get_k, get_leaf_start, transfer_from_vault,
+    },
+};
+
+#[derive(Accounts)]
+pub struct CrankDistribute<'info> {
+    #[account(
+        mut,
+        has_one = big_list,
+        constraint = batch_process.mode == DistributionMode::Push,
+        seeds = [b"batch_process".as_ref(), batch_process.id.as_ref(), batch_process.authority.as_ref(), batch_process.batch_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub batch_process: Account<'info, BatchProcess>,
+    pub big_list: Account<'info, BigList>,
+    #[account(
+        seeds = [get_j(batch_process.cursor()).to_string().as_ref(), batch_process.id.as_ref(), batch_process.authority.as_ref()],
+        bump,
+    )]
+    pub big_list_j: Account<'info, BigList>,
+    #[account(
+        seeds = [get_k(batch_process.cursor()).to_string().as_ref(), get_j(batch_process.cursor()).to_string().as_ref(), batch_process.id.as_ref(), batch_process.authority.as_ref()],
+        bump,
+    )]
+    pub big_list_k: Account<'info, BigList>,
+    #[account(
+        init_if_needed,
+        seeds = [b"receipts".as_ref(), batch_process.key().as_ref(), get_k(batch_process.cursor()).to_string().as_ref(), get_j(batch_process.cursor()).to_string().as_ref()],
+        bump,
+        space = DistributionReceipts::LEN,
+        payer = keeper,
+    )]
+    pub receipts: Account<'info, DistributionReceipts>,
+    #[account(mut, has_one = batch_process, seeds = [b"failures".as_ref(), batch_process.key().as_ref()], bump)]
+    pub failures: Account<'info, BatchFailures>,
+    #[account(mut, seeds = [b"vault".as_ref(), batch_process.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, CrankDistribute<'info>>,
+) -> Result<()> {
+    let batch_process = &mut ctx.accounts.batch_process;
+    let big_list_k = &ctx.accounts.big_list_k;
+
+    let bounty = match batch_process.trigger {
+        BatchTrigger::Crank { bounty } => bounty,
+        _ => panic!("Batch process is driven by a thread, not a crank"),
+    };
+    if batch_process.status == BatchProcessStatus::Completed {
+        panic!("Batch process is already completed")
+    }
+
+    // Keepers are paid per batch, so every batch has to be as full as the
+    // leaf allows
+    let recipients = ctx
+        .remaining_accounts
+        .len()
+        .saturating_sub(batch_process.action.extra_accounts().len());
+    let cursor = batch_process.cursor();
+    let leaf_offset = (batch_process.total_processed - get_leaf_start(cursor)) as usize;
+    let expected = [
+        batch_process.batch_size as usize,
+        big_list_k.elements.len() - leaf_offset,
+        (batch_process.total_elements - batch_process.total_processed) as usize,
+    ]
+    .into_iter()
+    .min()
+    .unwrap();
+    if recipients != expected {
+        panic!("Expected {} recipients, got {}", expected, recipients)
+    }
+
+    let vault = ctx.accounts.vault.to_account_info();
+    let vault_bump = *ctx.bumps.get("vault").unwrap();
+    let keeper = ctx.accounts.keeper.to_account_info();
+    let system_program = ctx.accounts.system_program.to_account_info();
+
+    let count = distribute_batch(
+        batch_process,
+        *ctx.bumps.get("batch_process").unwrap(),
+        None,
+        big_list_k,
+        &mut ctx.accounts.receipts,
+        &mut ctx.accounts.failures,
+        &vault,
+        vault_bump,
+        &keeper,
+        &system_program,
+        ctx.remaining_accounts,
+    )?;
+    advance_batch_process(batch_process, count);
+
+    // The vault only holds the bounties of the batches counted at the start
+    if batch_process.bounties == 0 {
+        msg!("No bounty left for keeper {}", keeper.key());
+        return Ok(());
+    }
+    batch_process.bounties -= 1;
+    transfer_from_vault(&vault, &keeper, &system_program, batch_process.key(), vault_bump, bounty)?;
+    msg!("Payed keeper {} {} Lamports", keeper.key(), bounty);
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::{BigList, IndexType},
+    utils::get_index,
+};
+
+#[derive(Accounts)]
+#[instruction(id: String, j: u8, k: u8)]
+pub struct Find<'info> {
+    #[account(
+        has_one = authority,
+        constraint = big_list.index_type == IndexType::SortedIndex,
+        seeds = [id.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub big_list: Account<'info, BigList>,
+    #[account(
+        seeds = [j.to_string().as_ref(), id.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub big_list_j: Account<'info, BigList>,
+    #[account(
+        seeds = [k.to_string().as_ref(), j.to_string().as_ref(), id.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub big_list_k: Account<'info, BigList>,
+    /// CHECK: can be anyone
+    pub authority: AccountInfo<'info>,
+}
+
+// Returns the index of `key` in a sorted list, or None when it is absent.
+// Clients pick `j` and `k` with `BigList::find_child` on the root and the
+// branch, which this checks again on-chain.
+pub fn process(ctx: Context<Find>, _id: String, j: u8, k: u8, key: Pubkey) -> Result<Option<u32>> {
+    match ctx.accounts.big_list.find_child(&key) {
+        None => return Ok(None),
+        Some(child) if child != j => panic!("{} is under branch {}", key, child),
+        Some(_) => {}
+    }
+    match ctx.accounts.big_list_j.find_child(&key) {
+        None => return Ok(None),
+        Some(child) if child != k => panic!("{} is under leaf {}", key, child),
+        Some(_) => {}
+    }
+
+    Ok(ctx
+        .accounts
+        .big_list_k
+        .elements
+        .binary_search(&key)
+        .ok()
+        .map(|l| get_index(j, k, l as u8)))
+}
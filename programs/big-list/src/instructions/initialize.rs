@@ -1,16 +1,17 @@
 use crate::{
-    state::BigList, utils::{get_j, get_k},
+    events::ListInitialized,
+    state::{BigList, ChildSummary, IndexType}, utils::{get_j, get_k},
 };
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
-#[instruction(id: String, depth: u8)]
+#[instruction(id: String, depth: u8, unique: bool, index_type: IndexType)]
 pub struct InitializeBigList<'info> {
     #[account(
         init,
         seeds = [id.as_ref(), authority.key().as_ref()],
         bump,
-        space = BigList::size(1),
+        space = BigList::branch_size(1),
         payer = signer
     )]
     pub big_list: Account<'info, BigList>,
@@ -18,7 +19,7 @@ pub struct InitializeBigList<'info> {
         init,
         seeds = [get_j(0 as u32).to_string().as_ref(), id.as_ref(), authority.key().as_ref()],
         bump,
-        space = BigList::size(1),
+        space = BigList::branch_size(1),
         payer = signer
     )]
     pub big_list_j: Account<'info, BigList>,
@@ -39,8 +40,10 @@ pub struct InitializeBigList<'info> {
 
 pub fn process<'a, 'b, 'c, 'info>(
     ctx: Context<'a, 'b, 'c, 'info, InitializeBigList<'info>>,
-    _id: String,
+    id: String,
     _depth: u8,
+    unique: bool,
+    index_type: IndexType,
 ) -> Result<()> {
     let big_list = &mut ctx.accounts.big_list;
     let big_list_j = &mut ctx.accounts.big_list_j;
@@ -49,8 +52,22 @@ pub fn process<'a, 'b, 'c, 'info>(
     let authority = &ctx.accounts.authority;
     let now = Clock::get().unwrap().unix_timestamp;
 
+    // `init` resets the summaries and index type, so they are set after
     big_list.init(0, 0, authority.key(), now, Some(big_list_j.key()));
     big_list_j.init(1, 0, authority.key(), now, Some(big_list_k.key()));
     big_list_k.init(2, 0, authority.key(), now, None);
+    big_list.unique = unique;
+
+    big_list.summaries = vec![ChildSummary::default()];
+    big_list_j.summaries = vec![ChildSummary::default()];
+    big_list.index_type = index_type;
+    big_list_j.index_type = index_type;
+    big_list_k.index_type = index_type;
+
+    emit!(ListInitialized {
+        big_list: big_list.key(),
+        authority: authority.key(),
+        id,
+    });
     Ok(())
 }
@@ -1,22 +1,27 @@
-use anchor_lang::{
-    prelude::*,
-    solana_program::{self, instruction::Instruction, system_program, pubkey},
-};
-use clockwork_sdk::{
-    self,
-    state::{Thread, Trigger},
-    ThreadProgram,
-};
+use anchor_lang::{prelude::*, solana_program::system_program};
+#[cfg(feature = "clockwork")]
+use anchor_lang::solana_program::{self, sysvar};
+#[cfg(feature = "clockwork")]
+use clockwork_sdk::{self, state::Trigger};
 
+#[cfg(feature = "clockwork")]
+use crate::{
+    constants::MAX_BATCH_SIZE,
+    events::BatchStarted,
+    instructions::batch_distribute_ix,
+    state::{DistributionMode, DistributionReceipts},
+    utils::{assert_batch_action, get_allocation, get_leaf_total},
+};
 use crate::{
-    state::{BatchProcess, BatchProcessStatus, BigList},
+    state::{BatchAction, BatchFailures, BatchProcess, BatchTrigger, BigList},
+    thread::{Thread, ThreadProgram},
     utils::{get_j, get_k},
 };
 
 #[derive(Accounts)]
-#[instruction(id: String)]
+#[instruction(id: String, batch_id: u64, batch_size: u8, trigger: BatchTrigger, amount: u64, action: BatchAction)]
 pub struct InitializeBatchProcess<'info> {
-    #[account(init, seeds = [b"batch_process".as_ref(), id.as_ref(), authority.key().as_ref()], bump, payer = authority, space = 8 + 1 + 3 + 32 + 32 + 8)]
+    #[account(init, seeds = [b"batch_process".as_ref(), id.as_ref(), authority.key().as_ref(), batch_id.to_le_bytes().as_ref()], bump, payer = authority, space = BatchProcess::size(&id, &trigger, &action))]
     pub batch_process: Account<'info, BatchProcess>,
     #[account(
         has_one = authority,
@@ -34,16 +39,38 @@ pub struct InitializeBatchProcess<'info> {
         bump,
     )]
     pub big_list_k: Account<'info, BigList>,
+    #[account(
+        init,
+        seeds = [b"failures".as_ref(), batch_process.key().as_ref()],
+        bump,
+        payer = authority,
+        space = BatchFailures::size(0),
+    )]
+    pub failures: Account<'info, BatchFailures>,
+    #[account(mut, seeds = [b"vault".as_ref(), batch_process.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
     #[account(mut)]
     pub authority: Signer<'info>,
-    #[account(mut, address = Thread::pubkey(batch_process.key(), id.to_string()))]
+    #[account(mut, address = Thread::pubkey(batch_process.key(), batch_id.to_string()))]
     pub batch_processor_thread: SystemAccount<'info>,
     pub thread_program: Program<'info, ThreadProgram>,
     #[account(address = system_program::ID)]
     pub system_program: Program<'info, System>,
 }
 
-pub fn process(ctx: Context<InitializeBatchProcess>, id: String) -> Result<()> {
+#[cfg(feature = "clockwork")]
+#[allow(clippy::too_many_arguments)]
+pub fn process(
+    ctx: Context<InitializeBatchProcess>,
+    id: String,
+    batch_id: u64,
+    batch_size: u8,
+    trigger: BatchTrigger,
+    amount: u64,
+    action: BatchAction,
+    shards: u8,
+    simulate: bool,
+) -> Result<()> {
     let batch_process = &mut ctx.accounts.batch_process;
     let big_list = &ctx.accounts.big_list;
     let big_list_j = &ctx.accounts.big_list_j;
@@ -53,37 +80,84 @@ pub fn process(ctx: Context<InitializeBatchProcess>, id: String) -> Result<()> {
     let authority = &ctx.accounts.authority;
     let batch_processor_thread = &ctx.accounts.batch_processor_thread;
 
-    batch_process.status = BatchProcessStatus::Processing;
-    batch_process.big_list = big_list.key();
-    batch_process.position = [0 as u8, 0 as u8, 0 as u8];
-    batch_process.total_processed = 0;
-    batch_process.authority = authority.key();
-
-    let mut accounts = vec![
-        AccountMeta::new(batch_process.key(), false),
-        AccountMeta::new_readonly(big_list.key(), false),
-        AccountMeta::new_readonly(big_list_j.key(), false),
-        AccountMeta::new_readonly(big_list_k.key(), false),
-        AccountMeta::new_readonly(authority.key(), false),
-        AccountMeta::new(batch_processor_thread.key(), true),
-        AccountMeta::new_readonly(thread_program.key(), false),
-        AccountMeta::new_readonly(system_program.key(), false),
-    ];
-
-    let first_20 = big_list_k.elements.iter().take(20);
-
-    for account in first_20 {
-        let meta =  AccountMeta::new(account.key(), false);
-        accounts.push(meta);
+    if batch_size == 0 || batch_size as usize > MAX_BATCH_SIZE {
+        panic!("Batch size must be between 1 and {}", MAX_BATCH_SIZE)
+    }
+    if let BatchTrigger::Crank { .. } = trigger {
+        panic!("Crank batch processes are created with initialize_crank_process")
+    }
+    assert_batch_action(&action, batch_size);
+    let leaves = get_leaf_total(big_list.total_elements);
+    if shards as u32 > leaves {
+        panic!("Can not split {} leaves into {} shards", leaves, shards)
     }
 
-    let create_batch_distribution_ix = Instruction {
-        program_id: crate::ID,
-        accounts: accounts,
-        data: clockwork_sdk::utils::anchor_sighash("batch_distribute").into(),
-    };
+    batch_process.init(
+        big_list.key(),
+        big_list.total_elements,
+        authority.key(),
+        id.clone(),
+        batch_id,
+        DistributionMode::Push,
+        amount,
+    );
+    batch_process.batch_size = batch_size;
+    batch_process.trigger = trigger.clone();
+    batch_process.action = action;
+    batch_process.shards = shards;
+    batch_process.simulate = simulate;
+
+    ctx.accounts.failures.batch_process = batch_process.key();
+    ctx.accounts.failures.owner = batch_process.key();
+
+    emit!(BatchStarted {
+        batch_process: batch_process.key(),
+        big_list: big_list.key(),
+        batch_id,
+        mode: batch_process.mode.clone(),
+        amount,
+        total_elements: big_list.total_elements,
+    });
+
+    let first_batch: Vec<Pubkey> = big_list_k
+        .elements
+        .iter()
+        .take(batch_size as usize)
+        .cloned()
+        .collect();
+
+    let create_batch_distribution_ix = batch_distribute_ix(
+        batch_process.key(),
+        batch_process,
+        big_list_j.key(),
+        big_list_k.key(),
+        DistributionReceipts::pubkey(batch_process.key(), 0, 0),
+        batch_processor_thread.key(),
+        &first_batch,
+    );
     // let trigger_insant = Trigger::Immediate,
 
+    // Fund every element's allocation, plus rent so the vault outlives the
+    // run. Simulated runs never pay out, so they only fund the rent.
+    let allocation = if simulate { 0 } else { get_allocation(amount, big_list.total_elements) };
+    let vault_lamports = allocation + Rent::get()?.minimum_balance(0);
+    solana_program::program::invoke(
+        &solana_program::system_instruction::transfer(
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.vault.key(),
+            vault_lamports,
+        ),
+        &[
+            ctx.accounts.authority.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    // Sharded runs start a thread per shard with `initialize_batch_shard`
+    if shards > 0 {
+        return Ok(());
+    }
 
     // Add 5 SOL to thread to cover fees
     solana_program::program::invoke(
@@ -99,23 +173,17 @@ pub fn process(ctx: Context<InitializeBatchProcess>, id: String) -> Result<()> {
         ],
     )?;
 
-
-    msg!("YOO");
-
     let authority_key = authority.clone().key();
+    let batch_id_bytes = batch_id.to_le_bytes();
 
     let batch_process_signer_seeds: &[&[u8]] = &[
         b"batch_process",
         id.as_ref(),
         authority_key.as_ref(),
+        batch_id_bytes.as_ref(),
         &[*ctx.bumps.get("batch_process").unwrap()],
     ];
 
-    let trigger = Trigger::Cron {
-        schedule: "*/30 * * * * * *".into(), // 30 sec
-        skippable: false
-    };
-
     clockwork_sdk::cpi::thread_create(
         CpiContext::new_with_signer(
             thread_program.to_account_info(),
@@ -127,9 +195,44 @@ pub fn process(ctx: Context<InitializeBatchProcess>, id: String) -> Result<()> {
             },
             &[batch_process_signer_seeds],
         ),
-        id.clone().into(),
+        batch_process.thread_id(),
         create_batch_distribution_ix.into(),
-        trigger,
+        thread_trigger(trigger),
     )?;
     Ok(())
 }
+
+#[cfg(feature = "clockwork")]
+pub fn thread_trigger(trigger: BatchTrigger) -> Trigger {
+    match trigger {
+        BatchTrigger::Immediate => Trigger::Immediate,
+        BatchTrigger::Cron { schedule } => Trigger::Cron {
+            schedule,
+            skippable: false,
+        },
+        // Clockwork has no slot trigger, so watch the slot in the clock
+        // sysvar and let the step skip until the interval passed.
+        BatchTrigger::SlotInterval { .. } => Trigger::Account {
+            address: sysvar::clock::ID,
+            offset: 0,
+            size: 8,
+        },
+        BatchTrigger::Crank { .. } => unreachable!(),
+    }
+}
+
+#[cfg(not(feature = "clockwork"))]
+#[allow(clippy::too_many_arguments)]
+pub fn process(
+    _ctx: Context<InitializeBatchProcess>,
+    _id: String,
+    _batch_id: u64,
+    _batch_size: u8,
+    _trigger: BatchTrigger,
+    _amount: u64,
+    _action: BatchAction,
+    _shards: u8,
+    _simulate: bool,
+) -> Result<()> {
+    crate::thread::unsupported()
+}
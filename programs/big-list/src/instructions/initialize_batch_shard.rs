@@ -0,0 +1,164 @@
+use anchor_lang::{prelude::*, solana_program::system_program};
+#[cfg(feature = "clockwork")]
+use anchor_lang::solana_program;
+
+#[cfg(feature = "clockwork")]
+use crate::{
+    instructions::{shard_distribute_ix, thread_trigger},
+    state::{BatchProcessStatus, DistributionReceipts},
+    utils::{get_allocation, get_j, get_k, get_shard_range, transfer_from_vault},
+};
+use crate::{
+    state::{BatchFailures, BatchProcess, BatchShard, BigList},
+    thread::{Thread, ThreadProgram},
+};
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct InitializeBatchShard<'info> {
+    #[account(
+        has_one = authority,
+        has_one = big_list,
+        seeds = [b"batch_process".as_ref(), batch_process.id.as_ref(), authority.key().as_ref(), batch_process.batch_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub batch_process: Account<'info, BatchProcess>,
+    #[account(
+        init,
+        seeds = [b"shard".as_ref(), batch_process.key().as_ref(), &[index]],
+        bump,
+        payer = authority,
+        space = BatchShard::LEN,
+    )]
+    pub shard: Account<'info, BatchShard>,
+    #[account(
+        init,
+        seeds = [b"failures".as_ref(), shard.key().as_ref()],
+        bump,
+        payer = authority,
+        space = BatchFailures::size(0),
+    )]
+    pub failures: Account<'info, BatchFailures>,
+    #[account(mut, seeds = [b"vault".as_ref(), batch_process.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+    #[account(mut, seeds = [b"vault".as_ref(), shard.key().as_ref()], bump)]
+    pub shard_vault: SystemAccount<'info>,
+    pub big_list: Account<'info, BigList>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut, address = Thread::pubkey(batch_process.key(), BatchShard::thread_id(batch_process.batch_id, index)))]
+    pub shard_thread: SystemAccount<'info>,
+    pub thread_program: Program<'info, ThreadProgram>,
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+// Starts the thread of one shard, called once per shard after
+// `initialize_batch_process`
+#[cfg(feature = "clockwork")]
+pub fn process(ctx: Context<InitializeBatchShard>, index: u8) -> Result<()> {
+    let batch_process = &ctx.accounts.batch_process;
+    let shard = &mut ctx.accounts.shard;
+    let authority = &ctx.accounts.authority;
+    let shard_thread = &ctx.accounts.shard_thread;
+
+    if index >= batch_process.shards {
+        panic!("Batch process only has {} shards", batch_process.shards)
+    }
+
+    let (start, end) = get_shard_range(batch_process.total_elements, batch_process.shards, index);
+    shard.batch_process = batch_process.key();
+    shard.index = index;
+    shard.status = BatchProcessStatus::Ready;
+    shard.start = start;
+    shard.end = end;
+    shard.total_processed = start;
+    shard.last_step_slot = 0;
+
+    ctx.accounts.failures.batch_process = batch_process.key();
+    ctx.accounts.failures.owner = shard.key();
+
+    // Move the shard's allocation to its own vault, the authority adds the
+    // rent so it outlives the run
+    if !batch_process.simulate {
+        transfer_from_vault(
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.shard_vault.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            batch_process.key(),
+            *ctx.bumps.get("vault").unwrap(),
+            get_allocation(batch_process.amount, end - start),
+        )?;
+    }
+    solana_program::program::invoke(
+        &solana_program::system_instruction::transfer(
+            &authority.key(),
+            &ctx.accounts.shard_vault.key(),
+            Rent::get()?.minimum_balance(0),
+        ),
+        &[
+            authority.to_account_info(),
+            ctx.accounts.shard_vault.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    // The first step only loads the shard's first leaf
+    let cursor = shard.cursor();
+    let (j, k) = (get_j(cursor), get_k(cursor));
+    let create_shard_distribution_ix = shard_distribute_ix(
+        batch_process.key(),
+        batch_process,
+        shard.key(),
+        BigList::pubkey(batch_process.authority, &batch_process.id, &[j]),
+        BigList::pubkey(batch_process.authority, &batch_process.id, &[j, k]),
+        DistributionReceipts::pubkey(batch_process.key(), j, k),
+        shard_thread.key(),
+        &[],
+    );
+
+    // Add 5 SOL to thread to cover fees
+    solana_program::program::invoke(
+        &solana_program::system_instruction::transfer(
+            &authority.key(),
+            &shard_thread.key(),
+            5_000_000_000,
+        ),
+        &[
+            authority.to_account_info(),
+            shard_thread.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    let batch_id_bytes = batch_process.batch_id.to_le_bytes();
+    let batch_process_signer_seeds: &[&[u8]] = &[
+        b"batch_process",
+        batch_process.id.as_ref(),
+        batch_process.authority.as_ref(),
+        batch_id_bytes.as_ref(),
+        &[*ctx.bumps.get("batch_process").unwrap()],
+    ];
+
+    clockwork_sdk::cpi::thread_create(
+        CpiContext::new_with_signer(
+            ctx.accounts.thread_program.to_account_info(),
+            clockwork_sdk::cpi::ThreadCreate {
+                authority: batch_process.to_account_info(),
+                payer: authority.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                thread: shard_thread.to_account_info(),
+            },
+            &[batch_process_signer_seeds],
+        ),
+        BatchShard::thread_id(batch_process.batch_id, index),
+        create_shard_distribution_ix.into(),
+        thread_trigger(batch_process.trigger.clone()),
+    )?;
+    Ok(())
+}
+
+#[cfg(not(feature = "clockwork"))]
+pub fn process(_ctx: Context<InitializeBatchShard>, _index: u8) -> Result<()> {
+    crate::thread::unsupported()
+}
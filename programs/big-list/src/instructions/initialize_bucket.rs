@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{BigList, SetBucket};
+
+#[derive(Accounts)]
+#[instruction(id: String, prefix: u8)]
+pub struct InitializeBucket<'info> {
+    #[account(
+        has_one = authority,
+        constraint = big_list.unique,
+        seeds = [id.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub big_list: Account<'info, BigList>,
+    #[account(
+        init,
+        seeds = [b"bucket".as_ref(), big_list.key().as_ref(), &[prefix]],
+        bump,
+        space = SetBucket::size(0),
+        payer = authority,
+    )]
+    pub bucket: Account<'info, SetBucket>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn process(ctx: Context<InitializeBucket>, _id: String, prefix: u8) -> Result<()> {
+    let bucket = &mut ctx.accounts.bucket;
+
+    bucket.big_list = ctx.accounts.big_list.key();
+    bucket.prefix = prefix;
+    bucket.keys = vec![];
+    Ok(())
+}
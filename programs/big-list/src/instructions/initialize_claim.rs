@@ -0,0 +1,144 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{self, system_program},
+};
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address},
+    token::{self, Transfer},
+};
+
+use crate::{
+    events::BatchStarted,
+    state::{BatchAction, BatchProcess, BatchProcessStatus, BatchTrigger, BigList, DistributionMode},
+    utils::get_allocation,
+};
+
+#[derive(Accounts)]
+#[instruction(id: String, batch_id: u64)]
+pub struct InitializeClaim<'info> {
+    #[account(
+        init,
+        seeds = [b"batch_process".as_ref(), id.as_ref(), authority.key().as_ref(), batch_id.to_le_bytes().as_ref()],
+        bump,
+        payer = authority,
+        space = BatchProcess::size(&id, &BatchTrigger::Immediate, &BatchAction::Transfer),
+    )]
+    pub batch_process: Account<'info, BatchProcess>,
+    #[account(
+        has_one = authority,
+        seeds = [id.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub big_list: Account<'info, BigList>,
+    #[account(mut, seeds = [b"vault".as_ref(), batch_process.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+// Token claims pass [mint, vault token account, authority token account,
+// token program, associated token program] as remaining accounts.
+pub fn process<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, InitializeClaim<'info>>,
+    id: String,
+    batch_id: u64,
+    amount: u64,
+    mint: Option<Pubkey>,
+    expires_at: i64,
+) -> Result<()> {
+    let batch_process = &mut ctx.accounts.batch_process;
+    let big_list = &ctx.accounts.big_list;
+    let authority = &ctx.accounts.authority;
+    let system_program = &ctx.accounts.system_program;
+
+    if expires_at <= Clock::get().unwrap().unix_timestamp {
+        panic!("Claims must expire in the future")
+    }
+
+    batch_process.init(
+        big_list.key(),
+        big_list.total_elements,
+        authority.key(),
+        id,
+        batch_id,
+        DistributionMode::Claim,
+        amount,
+    );
+    // Claims are open as soon as they are funded
+    batch_process.status = BatchProcessStatus::Processing;
+    batch_process.mint = mint;
+    batch_process.expires_at = expires_at;
+
+    emit!(BatchStarted {
+        batch_process: batch_process.key(),
+        big_list: big_list.key(),
+        batch_id,
+        mode: batch_process.mode.clone(),
+        amount,
+        total_elements: big_list.total_elements,
+    });
+
+    let total_amount = get_allocation(amount, big_list.total_elements);
+
+    match mint {
+        None => {
+            // Fund every element's allocation, plus rent so the vault outlives the claims
+            solana_program::program::invoke(
+                &solana_program::system_instruction::transfer(
+                    &authority.key(),
+                    &ctx.accounts.vault.key(),
+                    total_amount + Rent::get()?.minimum_balance(0),
+                ),
+                &[
+                    authority.to_account_info(),
+                    ctx.accounts.vault.to_account_info(),
+                    system_program.to_account_info(),
+                ],
+            )?;
+        }
+        Some(_) if ctx.remaining_accounts.len() < 5 => {
+            panic!("Token claims need the mint, the vault token account, the authority token account, the token program and the associated token program")
+        }
+        Some(mint) => {
+            let mint_info = &ctx.remaining_accounts[0];
+            let vault_token = &ctx.remaining_accounts[1];
+            let authority_token = &ctx.remaining_accounts[2];
+            let token_program = &ctx.remaining_accounts[3];
+
+            if mint_info.key() != mint {
+                panic!("Mint account does not match {}", mint)
+            }
+            if vault_token.key() != get_associated_token_address(&batch_process.key(), &mint) {
+                panic!("Vault is not the batch process token account")
+            }
+
+            associated_token::create(CpiContext::new(
+                ctx.remaining_accounts[4].clone(),
+                associated_token::Create {
+                    payer: authority.to_account_info(),
+                    associated_token: vault_token.clone(),
+                    authority: batch_process.to_account_info(),
+                    mint: mint_info.clone(),
+                    system_program: system_program.to_account_info(),
+                    token_program: token_program.clone(),
+                },
+            ))?;
+
+            token::transfer(
+                CpiContext::new(
+                    token_program.clone(),
+                    Transfer {
+                        from: authority_token.clone(),
+                        to: vault_token.clone(),
+                        authority: authority.to_account_info(),
+                    },
+                ),
+                total_amount,
+            )?;
+        }
+    }
+
+    Ok(())
+}
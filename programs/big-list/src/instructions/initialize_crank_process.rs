@@ -0,0 +1,118 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{self, system_program},
+};
+
+use crate::{
+    constants::MAX_BATCH_SIZE,
+    events::BatchStarted,
+    state::{BatchAction, BatchFailures, BatchProcess, BatchTrigger, BigList, DistributionMode},
+    utils::{assert_batch_action, get_allocation, get_batch_count},
+};
+
+#[derive(Accounts)]
+#[instruction(id: String, batch_id: u64, batch_size: u8, amount: u64, bounty: u64, action: BatchAction)]
+pub struct InitializeCrankProcess<'info> {
+    #[account(
+        init,
+        seeds = [b"batch_process".as_ref(), id.as_ref(), authority.key().as_ref(), batch_id.to_le_bytes().as_ref()],
+        bump,
+        payer = authority,
+        space = BatchProcess::size(&id, &BatchTrigger::Crank { bounty: 0 }, &action),
+    )]
+    pub batch_process: Account<'info, BatchProcess>,
+    #[account(
+        has_one = authority,
+        seeds = [id.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub big_list: Account<'info, BigList>,
+    #[account(
+        init,
+        seeds = [b"failures".as_ref(), batch_process.key().as_ref()],
+        bump,
+        payer = authority,
+        space = BatchFailures::size(0),
+    )]
+    pub failures: Account<'info, BatchFailures>,
+    #[account(mut, seeds = [b"vault".as_ref(), batch_process.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn process(
+    ctx: Context<InitializeCrankProcess>,
+    id: String,
+    batch_id: u64,
+    batch_size: u8,
+    amount: u64,
+    bounty: u64,
+    action: BatchAction,
+    simulate: bool,
+) -> Result<()> {
+    let batch_process = &mut ctx.accounts.batch_process;
+    let big_list = &ctx.accounts.big_list;
+    let authority = &ctx.accounts.authority;
+
+    if batch_size == 0 || batch_size as usize > MAX_BATCH_SIZE {
+        panic!("Batch size must be between 1 and {}", MAX_BATCH_SIZE)
+    }
+    assert_batch_action(&action, batch_size);
+
+    batch_process.init(
+        big_list.key(),
+        big_list.total_elements,
+        authority.key(),
+        id,
+        batch_id,
+        DistributionMode::Push,
+        amount,
+    );
+    batch_process.batch_size = batch_size;
+    batch_process.trigger = BatchTrigger::Crank { bounty };
+    batch_process.action = action;
+    batch_process.simulate = simulate;
+
+    ctx.accounts.failures.batch_process = batch_process.key();
+    ctx.accounts.failures.owner = batch_process.key();
+
+    emit!(BatchStarted {
+        batch_process: batch_process.key(),
+        big_list: big_list.key(),
+        batch_id,
+        mode: batch_process.mode.clone(),
+        amount,
+        total_elements: big_list.total_elements,
+    });
+
+    // Fund every element's allocation and a bounty per batch, plus rent so
+    // the vault outlives the run. Simulated runs never pay out, but keepers
+    // still step them, so they only fund the bounties and the rent.
+    batch_process.bounties = get_batch_count(big_list.total_elements, batch_size);
+    let bounties = bounty
+        .checked_mul(batch_process.bounties as u64)
+        .expect("Bounties do not fit in u64");
+    let allocation = if simulate { 0 } else { get_allocation(amount, big_list.total_elements) };
+    let vault_lamports = allocation
+        .checked_add(bounties)
+        .and_then(|lamports| lamports.checked_add(Rent::get().unwrap().minimum_balance(0)))
+        .expect("Vault funding does not fit in u64");
+    solana_program::program::invoke(
+        &solana_program::system_instruction::transfer(
+            &authority.key(),
+            &ctx.accounts.vault.key(),
+            vault_lamports,
+        ),
+        &[
+            authority.to_account_info(),
+            ctx.accounts.vault.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+        ],
+    )?;
+
+    Ok(())
+}
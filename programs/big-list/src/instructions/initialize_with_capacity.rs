@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::ListInitialized,
+    state::{BigList, IndexType},
+    utils::{get_branch_count, is_valid_capacity, MAX_ELEMENTS},
+};
+
+#[derive(Accounts)]
+#[instruction(id: String, capacity: u32)]
+pub struct InitializeWithCapacity<'info> {
+    #[account(
+        init,
+        seeds = [id.as_ref(), authority.key().as_ref()],
+        bump,
+        space = BigList::size(get_branch_count(capacity)),
+        payer = authority,
+    )]
+    pub big_list: Account<'info, BigList>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Creates the root of a list whose size is known up front. Branches and
+// leaves are created at full size with `allocate_leaves`, filled with
+// `write_range` and counted with `sync_totals`.
+pub fn process(ctx: Context<InitializeWithCapacity>, id: String, capacity: u32) -> Result<()> {
+    let big_list = &mut ctx.accounts.big_list;
+    let authority = &ctx.accounts.authority;
+
+    if !is_valid_capacity(capacity) {
+        panic!("Capacity must be between 1 and {}", MAX_ELEMENTS)
+    }
+
+    let branches = get_branch_count(capacity);
+    big_list.init(0, 0, authority.key(), Clock::get().unwrap().unix_timestamp, None);
+    big_list.index_type = IndexType::PrepaidIndex;
+    big_list.capacity = capacity;
+    // Filled in as `allocate_leaves` creates each branch
+    big_list.elements = vec![Pubkey::default(); branches];
+    big_list.len = branches as u16;
+
+    emit!(ListInitialized {
+        big_list: big_list.key(),
+        authority: authority.key(),
+        id,
+    });
+    Ok(())
+}
@@ -7,8 +7,56 @@ pub use append::*;
 pub mod append_rollover_k;
 pub use append_rollover_k::*;
 
+pub mod initialize_bucket;
+pub use initialize_bucket::*;
+
+pub mod find;
+pub use find::*;
+
+pub mod initialize_with_capacity;
+pub use initialize_with_capacity::*;
+
+pub mod allocate_leaves;
+pub use allocate_leaves::*;
+
+pub mod write_range;
+pub use write_range::*;
+
+pub mod sync_totals;
+pub use sync_totals::*;
+
 pub mod initialize_batch_process;
 pub use initialize_batch_process::*;
 
 pub mod batch_distribute;
 pub use batch_distribute::*;
+
+pub mod initialize_batch_shard;
+pub use initialize_batch_shard::*;
+
+pub mod shard_distribute;
+pub use shard_distribute::*;
+
+pub mod sync_shards;
+pub use sync_shards::*;
+
+pub mod initialize_claim;
+pub use initialize_claim::*;
+
+pub mod claim;
+pub use claim::*;
+
+pub mod reclaim;
+pub use reclaim::*;
+
+pub mod retry_failed;
+pub use retry_failed::*;
+
+pub mod initialize_crank_process;
+pub use initialize_crank_process::*;
+
+pub mod crank_distribute;
+pub use crank_distribute::CrankDistribute;
+pub(crate) use crank_distribute::__client_accounts_crank_distribute;
+#[cfg(feature = "cpi")]
+pub(crate) use crank_distribute::__cpi_client_accounts_crank_distribute;
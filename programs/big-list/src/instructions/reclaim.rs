@@ -0,0 +1,86 @@
+use anchor_lang::{prelude::*, solana_program::system_program};
+use anchor_spl::token::TokenAccount;
+
+use crate::{
+    state::{BatchFailures, BatchProcess, BatchProcessStatus, DistributionMode},
+    utils::{complete_batch_process, transfer_from_vault, transfer_tokens_from_vault},
+};
+
+#[derive(Accounts)]
+pub struct Reclaim<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        seeds = [b"batch_process".as_ref(), batch_process.id.as_ref(), authority.key().as_ref(), batch_process.batch_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub batch_process: Account<'info, BatchProcess>,
+    #[account(mut, seeds = [b"vault".as_ref(), batch_process.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+    /// CHECK: only read for push runs, claims never log failures
+    #[account(address = BatchFailures::pubkey(batch_process.key()))]
+    pub failures: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+// Claims are reclaimed once they expire, push runs once they completed with
+// nothing left for `retry_failed`. Shards keep what their own vaults hold.
+// Token runs pass [vault token account, authority token account, token
+// program] as remaining accounts.
+pub fn process<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, Reclaim<'info>>) -> Result<()> {
+    let batch_process = &ctx.accounts.batch_process;
+    let vault = &ctx.accounts.vault;
+
+    match batch_process.mode {
+        DistributionMode::Claim => {
+            if Clock::get().unwrap().unix_timestamp < batch_process.expires_at {
+                panic!("Claims are open until {}", batch_process.expires_at)
+            }
+        }
+        DistributionMode::Push => {
+            if batch_process.status != BatchProcessStatus::Completed {
+                panic!("Batch process is still running")
+            }
+            let failures = Account::<BatchFailures>::try_from(&ctx.accounts.failures)?;
+            if !failures.indices.is_empty() {
+                panic!("{} failed payments are left to retry", failures.indices.len())
+            }
+        }
+    }
+
+    if batch_process.mint.is_some() {
+        if ctx.remaining_accounts.len() < 3 {
+            panic!("Token reclaims need the vault token account, the authority token account and the token program")
+        }
+        let vault_token = &ctx.remaining_accounts[0];
+        let unclaimed = Account::<TokenAccount>::try_from(vault_token)?.amount;
+        transfer_tokens_from_vault(
+            batch_process,
+            *ctx.bumps.get("batch_process").unwrap(),
+            vault_token,
+            &ctx.remaining_accounts[1],
+            &ctx.remaining_accounts[2],
+            unclaimed,
+        )?;
+        msg!("Reclaimed {} unclaimed tokens", unclaimed);
+    }
+
+    let lamports = vault.lamports();
+    if lamports > 0 {
+        transfer_from_vault(
+            &vault.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            batch_process.key(),
+            *ctx.bumps.get("vault").unwrap(),
+            lamports,
+        )?;
+        msg!("Reclaimed {} lamports", lamports);
+    }
+
+    complete_batch_process(&mut ctx.accounts.batch_process);
+    Ok(())
+}
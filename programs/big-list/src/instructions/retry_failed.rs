@@ -0,0 +1,123 @@
+use anchor_lang::{prelude::*, solana_program::system_program};
+
+use crate::{
+    constants::MAX_LIST_VECTOR_SIZE,
+    events::BatchStepProcessed,
+    state::{BatchFailures, BatchProcess, BigList, DistributionMode, DistributionReceipts},
+    utils::{can_receive_lamports, get_leaf_offset, get_shard_range, transfer_from_vault},
+};
+
+#[derive(Accounts)]
+#[instruction(j: u8, k: u8)]
+pub struct RetryFailed<'info> {
+    #[account(
+        has_one = authority,
+        has_one = big_list,
+        constraint = batch_process.mode == DistributionMode::Push,
+        seeds = [b"batch_process".as_ref(), batch_process.id.as_ref(), authority.key().as_ref(), batch_process.batch_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub batch_process: Account<'info, BatchProcess>,
+    pub big_list: Account<'info, BigList>,
+    #[account(
+        seeds = [k.to_string().as_ref(), j.to_string().as_ref(), batch_process.id.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub big_list_k: Account<'info, BigList>,
+    #[account(
+        mut,
+        has_one = batch_process,
+        seeds = [b"receipts".as_ref(), batch_process.key().as_ref(), k.to_string().as_ref(), j.to_string().as_ref()],
+        bump,
+    )]
+    pub receipts: Account<'info, DistributionReceipts>,
+    // The batch process' failures, or a shard's for sharded runs
+    #[account(mut, has_one = batch_process, seeds = [b"failures".as_ref(), failures.owner.as_ref()], bump)]
+    pub failures: Account<'info, BatchFailures>,
+    #[account(mut, seeds = [b"vault".as_ref(), failures.owner.as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+// Remaining accounts are the recipients of the leaf's failed indices, in the
+// order they were logged. The run already counted them as processed when its
+// cursor passed them, so retries are reported without moving it.
+pub fn process<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, RetryFailed<'info>>,
+    j: u8,
+    k: u8,
+) -> Result<()> {
+    let batch_process = &ctx.accounts.batch_process;
+    let big_list_k = &ctx.accounts.big_list_k;
+    let receipts = &mut ctx.accounts.receipts;
+    let failures = &mut ctx.accounts.failures;
+
+    let leaf_start = get_leaf_offset(j, k);
+    let pending: Vec<u32> = failures
+        .indices
+        .iter()
+        .filter(|index| (leaf_start..leaf_start + MAX_LIST_VECTOR_SIZE as u32).contains(*index))
+        .cloned()
+        .collect();
+
+    if ctx.remaining_accounts.len() != pending.len() {
+        panic!("Expected {} recipients for leaf {},{}", pending.len(), j, k)
+    }
+
+    // Shards split on leaves, so the whole leaf belongs to the failures' shard
+    let shard = (failures.owner != batch_process.key()).then(|| {
+        (0..batch_process.shards)
+            .find(|index| {
+                let (start, end) =
+                    get_shard_range(batch_process.total_elements, batch_process.shards, *index);
+                (start..end).contains(&leaf_start)
+            })
+            .expect("Leaf is not in any shard")
+    });
+
+    let rent = Rent::get()?;
+    let mut resolved = vec![];
+
+    for (index, account_info) in pending.iter().zip(ctx.remaining_accounts) {
+        let offset = (index - leaf_start) as usize;
+        if account_info.key() != big_list_k.elements[offset] {
+            panic!("Recipient {} does not match list element {}", account_info.key(), index)
+        }
+
+        if receipts.is_paid(offset) {
+            resolved.push(*index);
+            continue;
+        }
+
+        if !can_receive_lamports(account_info, batch_process.amount, &rent) {
+            msg!("Failed to pay {} again, keeping index {}", account_info.key(), index);
+            continue;
+        }
+
+        transfer_from_vault(
+            &ctx.accounts.vault.to_account_info(),
+            account_info,
+            &ctx.accounts.system_program.to_account_info(),
+            failures.owner,
+            *ctx.bumps.get("vault").unwrap(),
+            batch_process.amount,
+        )?;
+        receipts.mark_paid(offset);
+        resolved.push(*index);
+
+        emit!(BatchStepProcessed {
+            batch_process: batch_process.key(),
+            shard,
+            start_index: *index,
+            count: 1,
+            total_processed: batch_process.total_processed,
+        });
+        msg!("Payed {} {} Lamports", account_info.key(), batch_process.amount);
+    }
+
+    failures.indices.retain(|index| !resolved.contains(index));
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, system_program},
+};
+
+#[cfg(feature = "clockwork")]
+use crate::{
+    instructions::{next_step_response, NextStep},
+    state::{BatchProcessStatus, BatchTrigger},
+    utils::{advance_shard, distribute_batch},
+};
+use crate::{
+    state::{
+        BatchFailures, BatchProcess, BatchShard, BigList, DistributionMode, DistributionReceipts,
+    },
+    thread::{self, anchor_sighash, Thread, ThreadProgram, ThreadResponse, PAYER_PUBKEY},
+    utils::{get_j, get_k},
+};
+
+// Shards only write accounts of their own, the batch process is read only
+// so the shards' threads run side by side.
+#[derive(Accounts)]
+pub struct ShardDistribute<'info> {
+    #[account(
+        has_one = authority,
+        has_one = big_list,
+        constraint = batch_process.mode == DistributionMode::Push,
+        seeds = [b"batch_process".as_ref(), batch_process.id.as_ref(), authority.key().as_ref(), batch_process.batch_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub batch_process: Account<'info, BatchProcess>,
+    #[account(
+        mut,
+        has_one = batch_process,
+        seeds = [b"shard".as_ref(), batch_process.key().as_ref(), &[shard.index]],
+        bump,
+    )]
+    pub shard: Account<'info, BatchShard>,
+    pub big_list: Account<'info, BigList>,
+    #[account(
+        seeds = [get_j(shard.cursor()).to_string().as_ref(), batch_process.id.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub big_list_j: Account<'info, BigList>,
+    #[account(
+        seeds = [get_k(shard.cursor()).to_string().as_ref(), get_j(shard.cursor()).to_string().as_ref(), batch_process.id.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub big_list_k: Account<'info, BigList>,
+    #[account(
+        init_if_needed,
+        seeds = [b"receipts".as_ref(), batch_process.key().as_ref(), get_k(shard.cursor()).to_string().as_ref(), get_j(shard.cursor()).to_string().as_ref()],
+        bump,
+        space = DistributionReceipts::LEN,
+        payer = payer,
+    )]
+    pub receipts: Account<'info, DistributionReceipts>,
+    #[account(mut, has_one = batch_process, seeds = [b"failures".as_ref(), shard.key().as_ref()], bump)]
+    pub failures: Account<'info, BatchFailures>,
+    #[account(mut, seeds = [b"vault".as_ref(), shard.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+    // Clockwork swaps in the worker's signatory and reimburses it from the thread
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: can be anyone
+    pub authority: AccountInfo<'info>,
+    #[account(mut, address = Thread::pubkey(batch_process.key(), BatchShard::thread_id(batch_process.batch_id, shard.index)))]
+    pub shard_thread: Signer<'info>,
+    pub thread_program: Program<'info, ThreadProgram>,
+    #[account(address = system_program::ID)]
+    pub system_program: Program<'info, System>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn shard_distribute_ix(
+    batch_process_key: Pubkey,
+    batch_process: &BatchProcess,
+    shard: Pubkey,
+    big_list_j: Pubkey,
+    big_list_k: Pubkey,
+    receipts: Pubkey,
+    shard_thread: Pubkey,
+    recipients: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(batch_process_key, false),
+        AccountMeta::new(shard, false),
+        AccountMeta::new_readonly(batch_process.big_list, false),
+        AccountMeta::new_readonly(big_list_j, false),
+        AccountMeta::new_readonly(big_list_k, false),
+        AccountMeta::new(receipts, false),
+        AccountMeta::new(BatchFailures::pubkey(shard), false),
+        AccountMeta::new(BatchProcess::vault(shard), false),
+        AccountMeta::new(PAYER_PUBKEY, true),
+        AccountMeta::new_readonly(batch_process.authority, false),
+        AccountMeta::new(shard_thread, true),
+        AccountMeta::new_readonly(thread::ID, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+
+    for recipient in recipients {
+        accounts.push(AccountMeta::new(*recipient, false));
+    }
+    accounts.extend(batch_process.action.extra_accounts());
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data: anchor_sighash("shard_distribute").into(),
+    }
+}
+
+#[cfg(feature = "clockwork")]
+pub fn process<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, ShardDistribute<'info>>,
+) -> Result<ThreadResponse> {
+    let batch_process = &ctx.accounts.batch_process;
+    let shard = &mut ctx.accounts.shard;
+    let big_list_k = &ctx.accounts.big_list_k;
+    let receipts = &mut ctx.accounts.receipts;
+
+    if shard.status == BatchProcessStatus::Completed {
+        return Ok(ThreadResponse::default());
+    }
+
+    // The thread wakes up every slot, only step once the interval has passed
+    let slot = Clock::get().unwrap().slot;
+    if let BatchTrigger::SlotInterval { interval } = batch_process.trigger {
+        if slot < shard.last_step_slot + interval {
+            return Ok(ThreadResponse::default());
+        }
+    }
+
+    let cursor = shard.cursor();
+
+    let count = distribute_batch(
+        batch_process,
+        *ctx.bumps.get("batch_process").unwrap(),
+        Some(&**shard),
+        big_list_k,
+        receipts,
+        &mut ctx.accounts.failures,
+        &ctx.accounts.vault.to_account_info(),
+        *ctx.bumps.get("vault").unwrap(),
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+        ctx.remaining_accounts,
+    )?;
+    advance_shard(shard, count);
+
+    if shard.status == BatchProcessStatus::Completed {
+        return Ok(ThreadResponse::default());
+    }
+
+    let next_step = NextStep::new(
+        batch_process,
+        &ctx.accounts.big_list_j,
+        big_list_k,
+        receipts.key(),
+        cursor,
+        shard.total_processed,
+        shard.end,
+    );
+
+    let next_shard_distribution_ix = shard_distribute_ix(
+        batch_process.key(),
+        batch_process,
+        shard.key(),
+        next_step.big_list_j,
+        next_step.big_list_k,
+        next_step.receipts,
+        ctx.accounts.shard_thread.key(),
+        &next_step.recipients,
+    );
+
+    Ok(next_step_response(&batch_process.trigger, next_shard_distribution_ix))
+}
+
+#[cfg(not(feature = "clockwork"))]
+pub fn process<'a, 'b, 'c, 'info>(
+    _ctx: Context<'a, 'b, 'c, 'info, ShardDistribute<'info>>,
+) -> Result<ThreadResponse> {
+    thread::unsupported()
+}
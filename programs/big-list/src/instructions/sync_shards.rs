@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::{BatchProcess, BatchProcessStatus, BatchShard},
+    utils::complete_batch_process,
+};
+
+#[derive(Accounts)]
+pub struct SyncShards<'info> {
+    #[account(
+        mut,
+        constraint = batch_process.shards > 0,
+        seeds = [b"batch_process".as_ref(), batch_process.id.as_ref(), batch_process.authority.as_ref(), batch_process.batch_id.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub batch_process: Account<'info, BatchProcess>,
+}
+
+// Remaining accounts are every shard of the batch process, in order. Their
+// progress is added up on the batch process, which completes once every
+// shard did. Anyone can sync, the totals only depend on the shards.
+pub fn process<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, SyncShards<'info>>) -> Result<()> {
+    let batch_process = &mut ctx.accounts.batch_process;
+
+    if ctx.remaining_accounts.len() != batch_process.shards as usize {
+        panic!("Expected the {} shards of the batch process", batch_process.shards)
+    }
+
+    let mut total_processed = 0;
+    let mut completed_shards = 0;
+    for (index, account_info) in ctx.remaining_accounts.iter().enumerate() {
+        let shard = Account::<BatchShard>::try_from(account_info)?;
+        if shard.batch_process != batch_process.key() || shard.index as usize != index {
+            panic!("Expected shard {} of the batch process at {}", index, account_info.key())
+        }
+        total_processed += shard.total_processed - shard.start;
+        if shard.status == BatchProcessStatus::Completed {
+            completed_shards += 1;
+        }
+    }
+
+    batch_process.total_processed = total_processed;
+    batch_process.completed_shards = completed_shards;
+    msg!("Batch Total: {}, {}/{} shards completed", total_processed, completed_shards, batch_process.shards);
+
+    if completed_shards == batch_process.shards {
+        complete_batch_process(batch_process);
+    } else if total_processed > 0 {
+        batch_process.status = BatchProcessStatus::Processing;
+    }
+    Ok(())
+}
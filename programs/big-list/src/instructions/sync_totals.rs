@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    state::{BigList, ChildSummary, IndexType},
+    utils::realloc_with_rent,
+};
+
+#[derive(Accounts)]
+#[instruction(id: String, j: u8)]
+pub struct SyncTotals<'info> {
+    #[account(
+        mut,
+        has_one = authority,
+        constraint = big_list.index_type == IndexType::PrepaidIndex,
+        seeds = [id.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub big_list: Account<'info, BigList>,
+    #[account(
+        mut,
+        seeds = [j.to_string().as_ref(), id.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub big_list_j: Account<'info, BigList>,
+    /// CHECK: can be anyone
+    pub authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Remaining accounts are the leaves from `k_start` of branch `j`. Their
+// summaries are copied to the branch, which is summarized on the root, and
+// both totals are recounted. Totals only cover a contiguous list, so leaves
+// are synced in order and every leaf before the last one written is full.
+pub fn process<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, SyncTotals<'info>>,
+    _id: String,
+    j: u8,
+    k_start: u8,
+) -> Result<()> {
+    let big_list = &mut ctx.accounts.big_list;
+    let big_list_j = &mut ctx.accounts.big_list_j;
+    let payer = ctx.accounts.payer.to_account_info();
+    let system_program = ctx.accounts.system_program.to_account_info();
+
+    let k_end = k_start as usize + ctx.remaining_accounts.len();
+    if k_end > big_list_j.elements.len() {
+        panic!("Branch {} only holds {} leaves", j, big_list_j.elements.len())
+    }
+
+    if big_list_j.summaries.len() < k_end {
+        big_list_j.summaries.resize(k_end, ChildSummary::default());
+    }
+    for (k, leaf) in (k_start as usize..).zip(ctx.remaining_accounts) {
+        if leaf.key() != big_list_j.elements[k] {
+            panic!("Expected leaf {},{} at {}", j, k, big_list_j.elements[k])
+        }
+        let big_list_k = Account::<BigList>::try_from(leaf)?;
+        big_list_j.summaries[k] = ChildSummary::of_leaf(&big_list_k);
+    }
+    if let Some(k) = big_list_j.summaries.iter().position(|leaf| leaf.count < 256) {
+        if let Some(next) = big_list_j.summaries[k + 1..].iter().position(|leaf| leaf.count > 0) {
+            panic!(
+                "Leaf {},{} holds {} elements but leaf {},{} follows it",
+                j,
+                k,
+                big_list_j.summaries[k].count,
+                j,
+                k + 1 + next
+            )
+        }
+    }
+    big_list_j.total_elements = big_list_j.summaries.iter().map(|child| child.count).sum();
+
+    if big_list.summaries.len() <= j as usize {
+        big_list.summaries.resize(j as usize + 1, ChildSummary::default());
+    }
+    big_list.summaries[j as usize] = ChildSummary::of_branch(&big_list_j.summaries);
+    if let Some(j) = big_list.summaries.iter().position(|branch| branch.count < 256 * 256) {
+        if big_list.summaries[j + 1..].iter().any(|branch| branch.count > 0) {
+            panic!("Branch {} is not full but later branches hold elements", j)
+        }
+    }
+    big_list.total_elements = big_list.summaries.iter().map(|child| child.count).sum();
+
+    // Summaries grow with the leaves synced so far, Anchor writes both
+    // nodes back once they have room
+    realloc_with_rent(
+        &big_list.to_account_info(),
+        &payer,
+        &system_program,
+        BigList::size(big_list.elements.len()) + big_list.summaries.len() * ChildSummary::LEN,
+    )?;
+    realloc_with_rent(
+        &big_list_j.to_account_info(),
+        &payer,
+        &system_program,
+        BigList::size(big_list_j.elements.len()) + big_list_j.summaries.len() * ChildSummary::LEN,
+    )?;
+    Ok(())
+}
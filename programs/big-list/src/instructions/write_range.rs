@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::ElementsWritten,
+    state::{BigList, IndexType},
+    utils::{get_j, get_k, get_leaf_start},
+};
+
+#[derive(Accounts)]
+#[instruction(id: String, start_index: u32)]
+pub struct WriteRange<'info> {
+    #[account(
+        has_one = authority,
+        constraint = big_list.index_type == IndexType::PrepaidIndex,
+        seeds = [id.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub big_list: Account<'info, BigList>,
+    #[account(
+        mut,
+        seeds = [get_k(start_index + 1).to_string().as_ref(), get_j(start_index + 1).to_string().as_ref(), id.as_ref(), authority.key().as_ref()],
+        bump,
+    )]
+    pub big_list_k: Account<'info, BigList>,
+    pub authority: Signer<'info>,
+}
+
+// Only the leaf is written, so writes to different leaves can land in the
+// same slot. The leaf counts what was written and `sync_totals` carries it
+// up to the branch and the root. Written elements are only replaced with
+// `overwrite`, so a replayed or misaddressed write can't clobber them.
+pub fn process(
+    ctx: Context<WriteRange>,
+    _id: String,
+    start_index: u32,
+    addresses: Vec<Pubkey>,
+    overwrite: bool,
+) -> Result<()> {
+    let big_list_k = &mut ctx.accounts.big_list_k;
+
+    let leaf_start = get_leaf_start(start_index + 1);
+    let offset = (start_index - leaf_start) as usize;
+    let written = big_list_k.len as usize;
+    if offset > written {
+        panic!("Writes must continue from index {}", leaf_start as usize + written)
+    }
+    if offset < written && !overwrite {
+        panic!("Index {} is already written, pass overwrite to replace it", start_index)
+    }
+    match big_list_k.elements.get_mut(offset..offset + addresses.len()) {
+        Some(elements) => elements.copy_from_slice(&addresses),
+        None => panic!("Writes can not span leaves"),
+    }
+
+    let written = std::cmp::max(written, offset + addresses.len());
+    big_list_k.len = written as u16;
+    big_list_k.total_elements = written as u32;
+
+    emit!(ElementsWritten {
+        big_list: ctx.accounts.big_list.key(),
+        start_index,
+        count: addresses.len() as u32,
+        leaf: [get_j(start_index + 1), get_k(start_index + 1)],
+    });
+    Ok(())
+}
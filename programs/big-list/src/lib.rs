@@ -4,9 +4,13 @@ pub mod instructions;
 pub mod state;
 
 pub mod constants;
+pub mod events;
+pub mod thread;
 pub mod utils;
 
 use crate::instructions::*;
+use crate::state::{BatchAction, BatchTrigger, IndexType};
+use crate::thread::ThreadResponse;
 
 declare_id!("2dcZKYRfijTg3TMU2xocaCKVv6LJTzzdwtLBbMUyKzKi");
 
@@ -19,23 +23,134 @@ pub mod big_list {
         ctx: Context<'a, 'b, 'c, 'info, InitializeBigList<'info>>,
         id: String,
         depth: u8,
+        unique: bool,
+        index_type: IndexType,
     ) -> Result<()> {
-        initialize::process(ctx, id, depth)
+        initialize::process(ctx, id, depth, unique, index_type)
     }
 
-    pub fn append(ctx: Context<Append>, id: String, addresses: Vec<Pubkey>) -> Result<()> {
+    pub fn append<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, Append<'info>>, id: String, addresses: Vec<Pubkey>) -> Result<()> {
         append::process(ctx, id, addresses)
     }
 
-    pub fn append_rollover_k(ctx: Context<AppendRolloverK>, id: String, addresses: Vec<Pubkey>) -> Result<()> {
+    pub fn append_rollover_k<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, AppendRolloverK<'info>>, id: String, addresses: Vec<Pubkey>) -> Result<()> {
         append_rollover_k::process(ctx, id, addresses)
     }
 
-    pub fn initialize_batch_process(ctx: Context<InitializeBatchProcess>, id: String) -> Result<()> {
-        initialize_batch_process::process(ctx, id)
+    pub fn initialize_bucket(ctx: Context<InitializeBucket>, id: String, prefix: u8) -> Result<()> {
+        initialize_bucket::process(ctx, id, prefix)
     }
 
-    pub fn batch_distribute<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, BatchDistribute<'info>>) -> Result<clockwork_sdk::state::ThreadResponse> {
+    pub fn find(ctx: Context<Find>, id: String, j: u8, k: u8, key: Pubkey) -> Result<Option<u32>> {
+        find::process(ctx, id, j, k, key)
+    }
+
+    pub fn initialize_with_capacity(ctx: Context<InitializeWithCapacity>, id: String, capacity: u32) -> Result<()> {
+        initialize_with_capacity::process(ctx, id, capacity)
+    }
+
+    pub fn allocate_leaves<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, AllocateLeaves<'info>>,
+        id: String,
+        j: u8,
+        k_start: u8,
+        count: u8,
+    ) -> Result<()> {
+        allocate_leaves::process(ctx, id, j, k_start, count)
+    }
+
+    pub fn write_range(
+        ctx: Context<WriteRange>,
+        id: String,
+        start_index: u32,
+        addresses: Vec<Pubkey>,
+        overwrite: bool,
+    ) -> Result<()> {
+        write_range::process(ctx, id, start_index, addresses, overwrite)
+    }
+
+    pub fn sync_totals<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SyncTotals<'info>>,
+        id: String,
+        j: u8,
+        k_start: u8,
+    ) -> Result<()> {
+        sync_totals::process(ctx, id, j, k_start)
+    }
+
+    pub fn initialize_claim<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, InitializeClaim<'info>>,
+        id: String,
+        batch_id: u64,
+        amount: u64,
+        mint: Option<Pubkey>,
+        expires_at: i64,
+    ) -> Result<()> {
+        initialize_claim::process(ctx, id, batch_id, amount, mint, expires_at)
+    }
+
+    pub fn claim<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, Claim<'info>>, index: u32) -> Result<()> {
+        claim::process(ctx, index)
+    }
+
+    pub fn reclaim<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, Reclaim<'info>>) -> Result<()> {
+        reclaim::process(ctx)
+    }
+
+    pub fn retry_failed<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, RetryFailed<'info>>,
+        j: u8,
+        k: u8,
+    ) -> Result<()> {
+        retry_failed::process(ctx, j, k)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_crank_process(
+        ctx: Context<InitializeCrankProcess>,
+        id: String,
+        batch_id: u64,
+        batch_size: u8,
+        amount: u64,
+        bounty: u64,
+        action: BatchAction,
+        simulate: bool,
+    ) -> Result<()> {
+        initialize_crank_process::process(ctx, id, batch_id, batch_size, amount, bounty, action, simulate)
+    }
+
+    pub fn crank_distribute<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, CrankDistribute<'info>>) -> Result<()> {
+        crank_distribute::process(ctx)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_batch_process(
+        ctx: Context<InitializeBatchProcess>,
+        id: String,
+        batch_id: u64,
+        batch_size: u8,
+        trigger: BatchTrigger,
+        amount: u64,
+        action: BatchAction,
+        shards: u8,
+        simulate: bool,
+    ) -> Result<()> {
+        initialize_batch_process::process(ctx, id, batch_id, batch_size, trigger, amount, action, shards, simulate)
+    }
+
+    pub fn initialize_batch_shard(ctx: Context<InitializeBatchShard>, index: u8) -> Result<()> {
+        initialize_batch_shard::process(ctx, index)
+    }
+
+    pub fn batch_distribute<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, BatchDistribute<'info>>) -> Result<ThreadResponse> {
         batch_distribute::process(ctx)
     }
+
+    pub fn shard_distribute<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, ShardDistribute<'info>>) -> Result<ThreadResponse> {
+        shard_distribute::process(ctx)
+    }
+
+    pub fn sync_shards<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, SyncShards<'info>>) -> Result<()> {
+        sync_shards::process(ctx)
+    }
 }
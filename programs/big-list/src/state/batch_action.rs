@@ -0,0 +1,155 @@
+use anchor_lang::{prelude::*, solana_program::instruction::Instruction};
+
+// Where an account of the instruction template comes from
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub enum ActionAccountSource {
+    // The list element being processed
+    Element,
+    // The batch process, signing with its seeds
+    BatchProcess,
+    // The vault, signing with its seeds
+    Vault,
+    // Any other account, passed after the recipients
+    Fixed { pubkey: Pubkey },
+}
+
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct ActionAccount {
+    pub source: ActionAccountSource,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+// What a batch does with each element
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub enum BatchAction {
+    // Transfer `amount` lamports from the vault
+    Transfer,
+    // Invoke `program_id` once per element with the templated accounts
+    Invoke {
+        program_id: Pubkey,
+        data: Vec<u8>,
+        accounts: Vec<ActionAccount>,
+    },
+}
+
+impl BatchAction {
+    // Accounts the action needs after the recipients: the fixed accounts of
+    // the template followed by the program to invoke.
+    pub fn extra_accounts(&self) -> Vec<AccountMeta> {
+        match self {
+            BatchAction::Transfer => vec![],
+            BatchAction::Invoke {
+                program_id,
+                accounts,
+                ..
+            } => {
+                let mut metas: Vec<AccountMeta> = accounts
+                    .iter()
+                    .filter_map(|account| match account.source {
+                        ActionAccountSource::Fixed { pubkey } => Some(AccountMeta {
+                            pubkey,
+                            is_signer: false,
+                            is_writable: account.is_writable,
+                        }),
+                        _ => None,
+                    })
+                    .collect();
+                metas.push(AccountMeta::new_readonly(*program_id, false));
+                metas
+            }
+        }
+    }
+
+    // Resolves the template for one element
+    pub fn instruction(
+        &self,
+        element: Pubkey,
+        batch_process: Pubkey,
+        vault: Pubkey,
+    ) -> Option<Instruction> {
+        match self {
+            BatchAction::Transfer => None,
+            BatchAction::Invoke {
+                program_id,
+                data,
+                accounts,
+            } => Some(Instruction {
+                program_id: *program_id,
+                accounts: accounts
+                    .iter()
+                    .map(|account| AccountMeta {
+                        pubkey: match account.source {
+                            ActionAccountSource::Element => element,
+                            ActionAccountSource::BatchProcess => batch_process,
+                            ActionAccountSource::Vault => vault,
+                            ActionAccountSource::Fixed { pubkey } => pubkey,
+                        },
+                        is_signer: account.is_signer,
+                        is_writable: account.is_writable,
+                    })
+                    .collect(),
+                data: data.clone(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(source: ActionAccountSource, is_signer: bool, is_writable: bool) -> ActionAccount {
+        ActionAccount {
+            source,
+            is_signer,
+            is_writable,
+        }
+    }
+
+    #[test]
+    pub fn it_resolves_every_account_source() {
+        let (program_id, fixed) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (element, batch_process, vault) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let action = BatchAction::Invoke {
+            program_id,
+            data: vec![1, 2, 3],
+            accounts: vec![
+                account(ActionAccountSource::Element, false, true),
+                account(ActionAccountSource::BatchProcess, true, false),
+                account(ActionAccountSource::Vault, true, true),
+                account(ActionAccountSource::Fixed { pubkey: fixed }, false, false),
+            ],
+        };
+
+        let instruction = action.instruction(element, batch_process, vault).unwrap();
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(instruction.data, vec![1, 2, 3]);
+        assert_eq!(
+            instruction.accounts,
+            vec![
+                AccountMeta::new(element, false),
+                AccountMeta::new_readonly(batch_process, true),
+                AccountMeta::new(vault, true),
+                AccountMeta::new_readonly(fixed, false),
+            ]
+        );
+
+        // Only the fixed accounts and the program ride along the batch
+        assert_eq!(
+            action.extra_accounts(),
+            vec![
+                AccountMeta::new_readonly(fixed, false),
+                AccountMeta::new_readonly(program_id, false),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn it_has_nothing_to_invoke_for_transfers() {
+        let keys = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(BatchAction::Transfer.instruction(keys.0, keys.1, keys.2), None);
+        assert!(BatchAction::Transfer.extra_accounts().is_empty());
+    }
+}
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+// Global indices `batch_distribute` could not pay, waiting for `retry_failed`.
+// Sharded runs keep one per shard, next to the shard's vault.
+#[account]
+pub struct BatchFailures {
+    pub batch_process: Pubkey,
+    // The batch process or shard both the failures and the vault derive from
+    pub owner: Pubkey,
+    pub indices: Vec<u32>,
+}
+
+impl BatchFailures {
+    pub const BASE_LEN: usize
+        = 8  // discriminator
+        + 32 // batch_process      Pubkey
+        + 32 // owner              Pubkey
+        + 4  // indices            (empty vector)
+        ;
+
+    pub fn size(items: usize) -> usize {
+        BatchFailures::BASE_LEN + (items * 4)
+    }
+
+    pub fn pubkey(owner: Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"failures", owner.as_ref()], &crate::ID).0
+    }
+}
@@ -1,16 +1,156 @@
 use anchor_lang::prelude::*;
 
+use super::BatchAction;
+
 #[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
 pub enum BatchProcessStatus {
+    // Created, waiting for its first step
     Ready,
     Processing,
+    Completed,
+}
+
+// How elements receive their allocation
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub enum DistributionMode {
+    // A thread pushes the allocation to every element
+    Push,
+    // Elements claim their allocation until `expires_at`
+    Claim,
+}
+
+// How the thread is woken up between batches
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub enum BatchTrigger {
+    // Every batch chains straight into the next one
+    Immediate,
+    // One batch per tick of the cron schedule
+    Cron { schedule: String },
+    // One batch every `interval` slots
+    SlotInterval { interval: u64 },
+    // Any keeper may step the batch and earn `bounty` lamports
+    Crank { bounty: u64 },
 }
 
 #[account]
 pub struct BatchProcess {
     pub status: BatchProcessStatus,
     pub big_list: Pubkey,
+    pub total_elements: u32,
     pub total_processed: u32,
     pub position: [u8; 3],
     pub authority: Pubkey,
-}
\ No newline at end of file
+    pub id: String,
+    pub batch_id: u64,
+    pub batch_size: u8,
+    pub trigger: BatchTrigger,
+    pub last_step_slot: u64,
+    pub mode: DistributionMode,
+    pub amount: u64,
+    pub mint: Option<Pubkey>,
+    pub expires_at: i64,
+    pub action: BatchAction,
+    pub shards: u8,
+    pub completed_shards: u8,
+    pub simulate: bool,
+    // Keeper bounties the vault still holds, one per funded batch
+    pub bounties: u32,
+}
+
+impl BatchProcess {
+    pub const BASE_LEN: usize
+        = 8  // discriminator
+        + 1  // status             u8
+        + 32 // big_list           Pubkey
+        + 4  // total_elements     u32
+        + 4  // total_processed    u32
+        + 3  // position           [u8; 3]
+        + 32 // authority          Pubkey
+        + 4  // id                 (empty string)
+        + 8  // batch_id           u64
+        + 1  // batch_size         u8
+        + 8  // last_step_slot     u64
+        + 1  // mode               u8
+        + 8  // amount             u64
+        + 33 // mint               Option<Pubkey>
+        + 8  // expires_at         i64
+        + 1  // shards             u8
+        + 1  // completed_shards   u8
+        + 1  // simulate           bool
+        + 4  // bounties           u32
+        ;
+
+    pub fn size(id: &str, trigger: &BatchTrigger, action: &BatchAction) -> usize {
+        BatchProcess::BASE_LEN
+            + id.len()
+            + trigger.try_to_vec().unwrap().len()
+            + action.try_to_vec().unwrap().len()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn init(
+        &mut self,
+        big_list: Pubkey,
+        total_elements: u32,
+        authority: Pubkey,
+        id: String,
+        batch_id: u64,
+        mode: DistributionMode,
+        amount: u64,
+    ) {
+        self.status = BatchProcessStatus::Ready;
+        self.big_list = big_list;
+        self.total_elements = total_elements;
+        self.total_processed = 0;
+        self.position = [0, 0, 0];
+        self.authority = authority;
+        self.id = id;
+        self.batch_id = batch_id;
+        self.batch_size = 0;
+        self.trigger = BatchTrigger::Immediate;
+        self.last_step_slot = 0;
+        self.mode = mode;
+        self.amount = amount;
+        self.mint = None;
+        self.expires_at = 0;
+        self.action = BatchAction::Transfer;
+        self.shards = 0;
+        self.completed_shards = 0;
+        self.simulate = false;
+        self.bounties = 0;
+    }
+
+    // A list backs any number of runs, each told apart by its `batch_id`
+    pub fn pubkey(authority: Pubkey, id: &str, batch_id: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"batch_process",
+                id.as_ref(),
+                authority.as_ref(),
+                batch_id.to_le_bytes().as_ref(),
+            ],
+            &crate::ID,
+        )
+        .0
+    }
+
+    // The thread is already scoped to the batch process, the id only has to
+    // fit Clockwork's 32 bytes
+    pub fn thread_id(&self) -> String {
+        self.batch_id.to_string()
+    }
+
+    // Holds the lamports being distributed, token runs use the associated
+    // token account of the batch process instead. Shards hold their share
+    // in a vault of their own.
+    pub fn vault(owner: Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"vault", owner.as_ref()], &crate::ID).0
+    }
+
+    // The `total` whose leaf holds the next element to process. Once the
+    // run is exhausted this stays on the last leaf so the accounts of a
+    // stale step still resolve.
+    pub fn cursor(&self) -> u32 {
+        std::cmp::min(self.total_processed + 1, self.total_elements)
+    }
+}
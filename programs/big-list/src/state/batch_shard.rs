@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use super::BatchProcessStatus;
+
+// One cursor of a sharded batch process, walking the elements in
+// `start..end` with its own thread
+#[account]
+pub struct BatchShard {
+    pub batch_process: Pubkey,
+    pub index: u8,
+    pub status: BatchProcessStatus,
+    pub start: u32,
+    pub end: u32,
+    pub total_processed: u32,
+    pub last_step_slot: u64,
+}
+
+impl BatchShard {
+    pub const LEN: usize
+        = 8  // discriminator
+        + 32 // batch_process      Pubkey
+        + 1  // index              u8
+        + 1  // status             u8
+        + 4  // start              u32
+        + 4  // end                u32
+        + 4  // total_processed    u32
+        + 8  // last_step_slot     u64
+        ;
+
+    pub fn pubkey(batch_process: Pubkey, index: u8) -> Pubkey {
+        Pubkey::find_program_address(&[b"shard", batch_process.as_ref(), &[index]], &crate::ID).0
+    }
+
+    // Shard threads share the batch process as authority
+    pub fn thread_id(batch_id: u64, index: u8) -> String {
+        format!("{}-{}", batch_id, index)
+    }
+
+    // Same as `BatchProcess::cursor`, bounded by the end of the shard
+    pub fn cursor(&self) -> u32 {
+        std::cmp::min(self.total_processed + 1, self.end)
+    }
+}
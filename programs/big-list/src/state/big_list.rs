@@ -1,4 +1,4 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
 
 // use super::IndexPermissions;
 
@@ -7,6 +7,52 @@ use anchor_lang::prelude::*;
 pub enum IndexType {
     GrowableIndex,
     PrepaidIndex,
+    // Elements only ever grow by pubkey, so lookups can binary search
+    SortedIndex,
+}
+
+// What a branch knows about one child without loading it. `hash` covers
+// the leaf's elements, or the hashes of a branch's children.
+#[derive(AnchorDeserialize, AnchorSerialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChildSummary {
+    pub count: u32,
+    pub first: Pubkey,
+    pub last: Pubkey,
+    pub hash: [u8; 32],
+}
+
+impl ChildSummary {
+    pub const LEN: usize
+        = 4  // count              u32
+        + 32 // first              Pubkey
+        + 32 // last               Pubkey
+        + 32 // hash               [u8; 32]
+        ;
+
+    // Covers the first `len` elements, the rest of a prepaid leaf is unwritten
+    pub fn of_leaf(leaf: &BigList) -> ChildSummary {
+        let elements = &leaf.elements[..leaf.len as usize];
+        if elements.is_empty() {
+            return ChildSummary::default();
+        }
+        let bytes: Vec<&[u8]> = elements.iter().map(|element| element.as_ref()).collect();
+        ChildSummary {
+            count: elements.len() as u32,
+            first: elements[0],
+            last: elements[elements.len() - 1],
+            hash: hashv(&bytes).to_bytes(),
+        }
+    }
+
+    pub fn of_branch(children: &[ChildSummary]) -> ChildSummary {
+        let hashes: Vec<&[u8]> = children.iter().map(|child| child.hash.as_ref()).collect();
+        ChildSummary {
+            count: children.iter().map(|child| child.count).sum(),
+            first: children[0].first,
+            last: children[children.len() - 1].last,
+            hash: hashv(&hashes).to_bytes(),
+        }
+    }
 }
 
 #[account]
@@ -18,8 +64,12 @@ pub struct BigList {
     pub depth: u8,
     pub index: u8,
     pub total_elements: u32,
+    // Elements a prepaid list was allocated for, zero for growable lists
+    pub capacity: u32,
     pub elements: Vec<Pubkey>,
     pub created_at: i64,
+    pub unique: bool,
+    pub summaries: Vec<ChildSummary>,
 }
 
 impl BigList {
@@ -32,14 +82,61 @@ impl BigList {
         + 1  // depth              u32
         + 1  // index              u8
         + 4  // total_elements     u32
+        + 4  // capacity           u32
         + 4  // elements           (empty vector)
         + 8  // created_at.        i64
+        + 1  // unique             bool
+        + 4  // summaries          (empty vector)
         ;
 
     pub fn size(items: usize) -> usize {
         BigList::BASE_LEN + (items * 32)
     }
 
+    // Branch nodes also keep a summary of every child
+    pub fn branch_size(items: usize) -> usize {
+        BigList::size(items) + (items * ChildSummary::LEN)
+    }
+
+    // The child whose first and last keys surround `key`, only meaningful
+    // for sorted lists
+    pub fn find_child(&self, key: &Pubkey) -> Option<u8> {
+        self.summaries
+            .binary_search_by(|summary| {
+                if summary.last < *key {
+                    std::cmp::Ordering::Less
+                } else if summary.first > *key {
+                    std::cmp::Ordering::Greater
+                } else {
+                    std::cmp::Ordering::Equal
+                }
+            })
+            .ok()
+            .map(|child| child as u8)
+    }
+
+    // The child holding the element at `index` under this branch, with the
+    // element's index inside that child
+    pub fn locate(&self, index: u32) -> Option<(u8, u32)> {
+        let mut offset = index;
+        for (child, summary) in self.summaries.iter().enumerate() {
+            if offset < summary.count {
+                return Some((child as u8, offset));
+            }
+            offset -= summary.count;
+        }
+        None
+    }
+
+    // `path` is empty for the root, `[j]` for a branch and `[j, k]` for a leaf.
+    pub fn pubkey(authority: Pubkey, id: &str, path: &[u8]) -> Pubkey {
+        let indices: Vec<String> = path.iter().rev().map(|i| i.to_string()).collect();
+        let mut seeds: Vec<&[u8]> = indices.iter().map(|i| i.as_bytes()).collect();
+        seeds.push(id.as_bytes());
+        seeds.push(authority.as_ref());
+        Pubkey::find_program_address(&seeds, &crate::ID).0
+    }
+
     pub fn init(
         &mut self,
         depth: u8,
@@ -54,6 +151,9 @@ impl BigList {
         self.depth = depth;
         self.index = index;
         self.created_at = created_at;
+        self.unique = false;
+        self.summaries = vec![];
+        self.capacity = 0;
         match first_element {
             Some(pubkey) => {
                 self.elements = vec![];
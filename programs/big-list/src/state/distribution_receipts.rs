@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MAX_LIST_VECTOR_SIZE;
+
+// One bit per element of a K leaf, set once that element has been paid
+#[account]
+pub struct DistributionReceipts {
+    pub batch_process: Pubkey,
+    pub leaf: [u8; 2],
+    pub count: u16,
+    pub paid: [u8; MAX_LIST_VECTOR_SIZE / 8],
+}
+
+impl DistributionReceipts {
+    pub const LEN: usize
+        = 8  // discriminator
+        + 32 // batch_process      Pubkey
+        + 2  // leaf               [u8; 2]
+        + 2  // count              u16
+        + 32 // paid               [u8; 32]
+        ;
+
+    pub fn pubkey(batch_process: Pubkey, j: u8, k: u8) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"receipts",
+                batch_process.as_ref(),
+                k.to_string().as_ref(),
+                j.to_string().as_ref(),
+            ],
+            &crate::ID,
+        )
+        .0
+    }
+
+    pub fn is_paid(&self, offset: usize) -> bool {
+        self.paid[offset / 8] & (1 << (offset % 8)) != 0
+    }
+
+    pub fn mark_paid(&mut self, offset: usize) {
+        if self.is_paid(offset) {
+            panic!("Element {} was already paid", offset)
+        }
+        self.paid[offset / 8] |= 1 << (offset % 8);
+        self.count += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gen_receipts() -> DistributionReceipts {
+        DistributionReceipts {
+            batch_process: Pubkey::default(),
+            leaf: [0, 0],
+            count: 0,
+            paid: [0; MAX_LIST_VECTOR_SIZE / 8],
+        }
+    }
+
+    #[test]
+    pub fn it_marks_each_offset_once() {
+        let mut receipts = gen_receipts();
+        for offset in [0, 7, 8, 255] {
+            assert!(!receipts.is_paid(offset));
+            receipts.mark_paid(offset);
+            assert!(receipts.is_paid(offset));
+        }
+        assert!(!receipts.is_paid(1));
+        assert!(!receipts.is_paid(254));
+        assert_eq!(receipts.count, 4);
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn it_refuses_to_pay_twice() {
+        let mut receipts = gen_receipts();
+        receipts.mark_paid(42);
+        receipts.mark_paid(42);
+    }
+}
@@ -1,5 +1,20 @@
 mod big_list;
 pub use self::big_list::*;
 
+mod set_bucket;
+pub use self::set_bucket::*;
+
 mod batch_process;
-pub use self::batch_process::*;
\ No newline at end of file
+pub use self::batch_process::*;
+
+mod batch_action;
+pub use self::batch_action::*;
+
+mod batch_shard;
+pub use self::batch_shard::*;
+
+mod distribution_receipts;
+pub use self::distribution_receipts::*;
+
+mod batch_failures;
+pub use self::batch_failures::*;
@@ -0,0 +1,133 @@
+use std::cmp::Ordering;
+
+use anchor_lang::{prelude::*, solana_program::system_instruction::MAX_PERMITTED_DATA_LENGTH};
+
+// Sorted elements of a unique list whose first byte is `prefix`, so a
+// duplicate check only loads one bucket per element. Appends search and
+// grow the account data in place instead of deserializing the bucket, so
+// it is bound by the 10 MiB account limit rather than the 32 KiB heap.
+#[account]
+pub struct SetBucket {
+    pub big_list: Pubkey,
+    pub prefix: u8,
+    pub keys: Vec<Pubkey>,
+}
+
+impl SetBucket {
+    pub const BASE_LEN: usize
+        = 8  // discriminator
+        + 32 // big_list           Pubkey
+        + 1  // prefix             u8
+        + 4  // keys               (empty vector)
+        ;
+
+    // Where the key count starts, after the discriminator, list and prefix
+    const LEN_OFFSET: usize = 8 + 32 + 1;
+
+    // About 327K keys per prefix, so 83M elements for an evenly spread list
+    pub const MAX_KEYS: usize = (MAX_PERMITTED_DATA_LENGTH as usize - SetBucket::BASE_LEN) / 32;
+
+    pub fn size(items: usize) -> usize {
+        SetBucket::BASE_LEN + (items * 32)
+    }
+
+    pub fn pubkey(big_list: Pubkey, prefix: u8) -> Pubkey {
+        Pubkey::find_program_address(&[b"bucket", big_list.as_ref(), &[prefix]], &crate::ID).0
+    }
+
+    // False when the key is already in the bucket
+    pub fn insert(&mut self, key: Pubkey) -> bool {
+        match self.keys.binary_search(&key) {
+            Ok(_) => false,
+            Err(position) => {
+                self.keys.insert(position, key);
+                true
+            }
+        }
+    }
+
+    // The list and prefix of a bucket's account data
+    pub fn header(data: &[u8]) -> (Pubkey, u8) {
+        (Pubkey::new(&data[8..40]), data[40])
+    }
+
+    pub fn key_count(data: &[u8]) -> usize {
+        u32::from_le_bytes(data[SetBucket::LEN_OFFSET..SetBucket::BASE_LEN].try_into().unwrap()) as usize
+    }
+
+    // `insert` on a bucket's account data, which must have room for one
+    // more key
+    pub fn insert_in_place(data: &mut [u8], key: Pubkey) -> bool {
+        let len = SetBucket::key_count(data);
+        let keys = &data[SetBucket::BASE_LEN..SetBucket::size(len)];
+        let (mut low, mut high) = (0, len);
+        while low < high {
+            let middle = (low + high) / 2;
+            match keys[middle * 32..(middle + 1) * 32].cmp(key.as_ref()) {
+                Ordering::Equal => return false,
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+            }
+        }
+
+        let position = SetBucket::size(low);
+        data.copy_within(position..SetBucket::size(len), position + 32);
+        data[position..position + 32].copy_from_slice(key.as_ref());
+        data[SetBucket::LEN_OFFSET..SetBucket::BASE_LEN].copy_from_slice(&(len as u32 + 1).to_le_bytes());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn it_keeps_keys_sorted_and_unique() {
+        let mut bucket = SetBucket {
+            big_list: Pubkey::default(),
+            prefix: 0,
+            keys: vec![],
+        };
+        let keys: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        for key in keys.iter().rev() {
+            assert!(bucket.insert(*key));
+        }
+        assert!(!bucket.insert(keys[2]));
+        assert_eq!(bucket.keys, keys);
+    }
+
+    #[test]
+    pub fn it_inserts_in_place_like_the_account() {
+        let mut bucket = SetBucket {
+            big_list: Pubkey::new_unique(),
+            prefix: 7,
+            keys: vec![],
+        };
+        let keys: Vec<Pubkey> = (0..2000).map(|_| Pubkey::new_unique()).collect();
+        let mut data = vec![];
+        bucket.try_serialize(&mut data).unwrap();
+
+        // Past the ~1000 keys a deserialized bucket could take
+        for key in keys.iter().rev() {
+            assert!(bucket.insert(*key));
+            data.resize(SetBucket::size(bucket.keys.len()), 0);
+            assert!(SetBucket::insert_in_place(&mut data, *key));
+        }
+        data.resize(data.len() + 32, 0);
+        assert!(!SetBucket::insert_in_place(&mut data, keys[1000]));
+        data.truncate(data.len() - 32);
+
+        assert_eq!(SetBucket::header(&data), (bucket.big_list, 7));
+        assert_eq!(SetBucket::key_count(&data), 2000);
+        let read = SetBucket::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(read.keys, keys);
+    }
+
+    #[test]
+    pub fn it_fits_the_largest_bucket_in_an_account() {
+        let limit = MAX_PERMITTED_DATA_LENGTH as usize;
+        assert!(SetBucket::size(SetBucket::MAX_KEYS) <= limit);
+        assert!(SetBucket::size(SetBucket::MAX_KEYS + 1) > limit);
+    }
+}
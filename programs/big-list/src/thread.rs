@@ -0,0 +1,59 @@
+// The Clockwork items the thread instructions name in their accounts.
+// Without the `clockwork` feature they are stand-ins with the same
+// addresses, so the instructions stay declared but reject every call.
+
+#[cfg(feature = "clockwork")]
+pub use clockwork_sdk::{
+    state::{Thread, ThreadResponse},
+    utils::{anchor_sighash, PAYER_PUBKEY},
+    ThreadProgram, ID,
+};
+
+#[cfg(not(feature = "clockwork"))]
+pub use stand_in::*;
+
+#[cfg(not(feature = "clockwork"))]
+mod stand_in {
+    use anchor_lang::{prelude::*, solana_program::hash::hash};
+
+    declare_id!("3XXuUFfweXBwFgFfYaejLvZE4cGZiHgKiGfMtdxNzYmv");
+
+    pub use payer::ID as PAYER_PUBKEY;
+
+    mod payer {
+        anchor_lang::declare_id!("C1ockworkPayer11111111111111111111111111111");
+    }
+
+    #[derive(Clone)]
+    pub struct ThreadProgram;
+
+    impl anchor_lang::Id for ThreadProgram {
+        fn id() -> Pubkey {
+            ID
+        }
+    }
+
+    pub struct Thread;
+
+    impl Thread {
+        pub fn pubkey(authority: Pubkey, id: String) -> Pubkey {
+            Pubkey::find_program_address(&[b"thread", authority.as_ref(), id.as_bytes()], &ID).0
+        }
+    }
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Default)]
+    pub struct ThreadResponse {}
+
+    pub fn anchor_sighash(name: &str) -> [u8; 8] {
+        let mut sighash = [0u8; 8];
+        sighash.copy_from_slice(&hash(format!("global:{}", name).as_bytes()).to_bytes()[..8]);
+        sighash
+    }
+}
+
+// What the thread instructions do when the program is built without
+// Clockwork
+#[cfg(not(feature = "clockwork"))]
+pub fn unsupported() -> ! {
+    panic!("Thread instructions need the program built with the clockwork feature")
+}
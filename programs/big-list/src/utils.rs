@@ -1,64 +1,449 @@
-use crate::{constants::MAX_LIST_VECTOR_SIZE, state::BigList};
+use anchor_lang::{prelude::*, solana_program, Discriminator};
+use anchor_spl::{
+    associated_token::get_associated_token_address,
+    token::{self, Transfer},
+};
 
-pub fn get_j(total: u32) -> u8 {
-    if total == 0 {
-        return 0;
+use crate::{
+    constants::MAX_BATCH_SIZE,
+    events::{BatchCompleted, BatchStepProcessed, DistributionSimulated},
+    state::{
+        ActionAccountSource, BatchAction, BatchFailures, BatchProcess, BatchProcessStatus,
+        BatchShard, BigList, ChildSummary, DistributionReceipts, SetBucket,
+    },
+};
+
+// List geometry lives in `big_list_core`, shared with clients
+pub use big_list_core::{
+    is_valid_capacity, MAX_ELEMENTS,
+    get_branch_count, get_current_indices, get_index, get_j, get_k, get_l, get_leaf_capacity,
+    get_leaf_count, get_leaf_len, get_leaf_offset, get_leaf_start, get_leaf_total, get_total,
+    needs_rollover, split_rollover,
+};
+
+pub fn transfer_from_vault<'info>(
+    vault: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    batch_process: Pubkey,
+    vault_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    solana_program::program::invoke_signed(
+        &solana_program::system_instruction::transfer(vault.key, to.key, amount),
+        &[vault.clone(), to.clone(), system_program.clone()],
+        &[&[b"vault", batch_process.as_ref(), &[vault_bump]]],
+    )?;
+    Ok(())
+}
+
+// The token vault is the batch process' associated token account, so the
+// batch process signs for it.
+pub fn transfer_tokens_from_vault<'info>(
+    batch_process: &Account<'info, BatchProcess>,
+    batch_process_bump: u8,
+    vault: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let mint = batch_process.mint.unwrap();
+    if vault.key() != get_associated_token_address(&batch_process.key(), &mint) {
+        panic!("Vault is not the batch process token account")
+    }
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            Transfer {
+                from: vault.clone(),
+                to: to.clone(),
+                authority: batch_process.to_account_info(),
+            },
+            &[&[
+                b"batch_process",
+                batch_process.id.as_ref(),
+                batch_process.authority.as_ref(),
+                batch_process.batch_id.to_le_bytes().as_ref(),
+                &[batch_process_bump],
+            ]],
+        ),
+        amount,
+    )
+}
+
+// Paying an executable, or leaving the recipient below rent exemption,
+// would abort the whole transaction.
+pub fn can_receive_lamports(recipient: &AccountInfo, amount: u64, rent: &Rent) -> bool {
+    !recipient.executable && rent.is_exempt(recipient.lamports() + amount, recipient.data_len())
+}
+
+pub fn realloc_with_rent<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    let minimum_balance = Rent::get()?.minimum_balance(new_len);
+    if minimum_balance > account.lamports() {
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                payer.key,
+                account.key,
+                minimum_balance - account.lamports(),
+            ),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    account.realloc(new_len, false)?;
+    Ok(())
+}
+
+// Sorted lists only take elements greater than the last one
+pub fn assert_sorted_after(last: Option<Pubkey>, addresses: &[Pubkey]) {
+    let mut previous = last;
+    for address in addresses {
+        if let Some(previous) = previous {
+            if *address <= previous {
+                panic!("{} does not come after {}", address, previous)
+            }
+        }
+        previous = Some(*address);
     }
-    if total > 256 * 256 * 256 {
-        panic!("total is too large")
+}
+
+// Refreshes the summary of leaf `k` on its branch and of branch `j` on the
+// root, after elements were appended to the leaf
+pub fn update_summaries(
+    big_list: &mut BigList,
+    big_list_j: &mut BigList,
+    j: u8,
+    k: u8,
+    leaf: &BigList,
+) {
+    let summary = ChildSummary::of_leaf(leaf);
+    match big_list_j.summaries.get_mut(k as usize) {
+        Some(existing) => *existing = summary,
+        None => big_list_j.summaries.push(summary),
     }
-    let input = total - 1;
-    let j = (input / (256 * 256)) as u8;
-    return j;
+    big_list.summaries[j as usize] = ChildSummary::of_branch(&big_list_j.summaries);
 }
 
-pub fn get_k(total: u32) -> u8 {
-    if total == 0 {
-        return 0;
+// Unique lists pass the bucket of every appended element's prefix as
+// remaining accounts, each bucket once. Buckets are only read from their
+// header and grown in place, so they can outgrow the heap.
+pub fn insert_into_buckets<'info>(
+    big_list: &Account<'info, BigList>,
+    addresses: &[Pubkey],
+    buckets: &[AccountInfo<'info>],
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let mut prefixes: Vec<u8> = vec![];
+    for account_info in buckets {
+        let data = account_info.try_borrow_data()?;
+        if account_info.owner != &crate::ID
+            || data.len() < SetBucket::BASE_LEN
+            || data[..8] != SetBucket::discriminator()
+        {
+            panic!("{} is not a bucket", account_info.key())
+        }
+        let (owner, prefix) = SetBucket::header(&data);
+        if owner != big_list.key() {
+            panic!("Bucket {} belongs to another list", account_info.key())
+        }
+        if prefixes.contains(&prefix) {
+            panic!("Bucket {} was passed twice", prefix)
+        }
+        prefixes.push(prefix);
+    }
+
+    for address in addresses {
+        let prefix = address.to_bytes()[0];
+        if !prefixes.contains(&prefix) {
+            panic!("Missing bucket {} for {}", prefix, address)
+        }
     }
-    if total > 256 * 256 * 256 {
-        panic!("total is too large")
+
+    for (account_info, prefix) in buckets.iter().zip(prefixes) {
+        let inserts = addresses.iter().filter(|address| address.to_bytes()[0] == prefix);
+        let len = SetBucket::key_count(&account_info.try_borrow_data()?);
+        let next_len = len + inserts.clone().count();
+        if next_len > SetBucket::MAX_KEYS {
+            panic!("Bucket {} can not hold more than {}", prefix, SetBucket::MAX_KEYS)
+        }
+        realloc_with_rent(account_info, payer, system_program, SetBucket::size(next_len))?;
+
+        let mut data = account_info.try_borrow_mut_data()?;
+        for address in inserts {
+            if !SetBucket::insert_in_place(&mut data, *address) {
+                panic!("{} is already in the list", address)
+            }
+        }
     }
+    Ok(())
+}
 
-    println!("total: {}", total);
+// Splits the list into `shards` runs of whole K leaves, so shards never
+// share a leaf's receipts
+pub fn get_shard_range(total_elements: u32, shards: u8, index: u8) -> (u32, u32) {
+    let leaves = get_leaf_total(total_elements);
+    let start = leaves * index as u32 / shards as u32 * 256;
+    let end = leaves * (index as u32 + 1) / shards as u32 * 256;
+    (start, std::cmp::min(end, total_elements))
+}
 
-    let input = total - 1;
-    let k = input % ((256 * 256) as u32) / 256;
-    return k as u8;
+// Lamports or tokens needed to pay every element once
+pub fn get_allocation(amount: u64, total_elements: u32) -> u64 {
+    amount
+        .checked_mul(total_elements as u64)
+        .expect("Allocation does not fit in u64")
 }
 
-pub fn get_l(total: u32) -> u8 {
-    if total == 0 {
-        return 0;
+// Batches never span leaves, so every leaf is split on its own
+pub fn get_batch_count(total_elements: u32, batch_size: u8) -> u32 {
+    let batches = |len: u32| (len + batch_size as u32 - 1) / batch_size as u32;
+    (total_elements / 256) * batches(256) + batches(total_elements % 256)
+}
+
+pub fn assert_recipients_match_list(recipients: &[AccountInfo], elements: &[Pubkey]) {
+    for (recipient, element) in recipients.iter().zip(elements) {
+        if recipient.key() != *element {
+            panic!("Recipient {} does not match list element {}", recipient.key(), element)
+        }
     }
-    if total > 256 * 256 * 256 {
-        panic!("total is too large")
+}
+
+// The action's accounts ride along every batch, so they come out of the
+// recipients' share of the transaction.
+pub fn assert_batch_action(action: &BatchAction, batch_size: u8) {
+    let extra_accounts = action.extra_accounts().len();
+    if batch_size as usize + extra_accounts > MAX_BATCH_SIZE {
+        panic!(
+            "Batch size {} leaves no room for {} action accounts",
+            batch_size, extra_accounts
+        )
+    }
+
+    if let BatchAction::Invoke {
+        program_id,
+        accounts,
+        ..
+    } = action
+    {
+        if *program_id == crate::ID {
+            panic!("Batch actions can not invoke the big list program")
+        }
+        for account in accounts {
+            let can_sign = matches!(
+                account.source,
+                ActionAccountSource::BatchProcess | ActionAccountSource::Vault
+            );
+            if account.is_signer && !can_sign {
+                panic!("Only the batch process and the vault can sign batch actions")
+            }
+        }
     }
-    let input = total - 1;
-    let l = (input % 256) as u8;
-    return l;
 }
 
-pub fn get_current_indices(total_elements: u32) -> (u8, u8, u8) {
-    if total_elements == 0 {
-        return (0, 0, 0);
+// Pays the batch of recipients after the cursor from the vault, or invokes
+// the batch action for each of them, and returns how many it went through.
+// Shared by the Clockwork threads and the keeper crank, the action's
+// accounts follow the recipients. Sharded runs walk the shard's cursor and
+// pay from its own vault, `failures.owner` is the account the vault derives
+// from.
+#[allow(clippy::too_many_arguments)]
+pub fn distribute_batch<'info>(
+    batch_process: &Account<'info, BatchProcess>,
+    batch_process_bump: u8,
+    shard: Option<&BatchShard>,
+    big_list_k: &Account<'info, BigList>,
+    receipts: &mut Account<'info, DistributionReceipts>,
+    failures: &mut Account<'info, BatchFailures>,
+    vault: &AccountInfo<'info>,
+    vault_bump: u8,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<u32> {
+    let action = batch_process.action.clone();
+    let extra_accounts = action.extra_accounts();
+    if remaining_accounts.len() < extra_accounts.len() {
+        panic!("Missing the {} batch action accounts", extra_accounts.len())
     }
-    if total_elements > 256 * 256 * 256 {
-        panic!("total is too large")
+    let (recipients, action_accounts) =
+        remaining_accounts.split_at(remaining_accounts.len() - extra_accounts.len());
+    for (account_info, meta) in action_accounts.iter().zip(&extra_accounts) {
+        if account_info.key() != meta.pubkey {
+            panic!("Expected batch action account {}", meta.pubkey)
+        }
     }
-    let input = total_elements - 1;
-    let j = (input / (256 * 256)) as u8;
-    let k = input % ((256 * 256) as u32) / 256;
-    let l = input % 256;
 
-    println!("j,k,l {},{},{} ", j, k, l);
-    return (j as u8, k as u8, l as u8);
+    let (processed, end) = match shard {
+        Some(shard) => (shard.total_processed, shard.end),
+        None => (batch_process.total_processed, batch_process.total_elements),
+    };
+    let cursor = std::cmp::min(processed + 1, end);
+    let leaf_offset = (processed - get_leaf_start(cursor)) as usize;
+
+    let batch_len = recipients.len();
+    if batch_len > batch_process.batch_size as usize {
+        panic!("Batches can not hold more than {} recipients", batch_process.batch_size)
+    }
+    if processed + batch_len as u32 > end {
+        panic!("Recipients run past the end of the batch process")
+    }
+    match big_list_k.elements.get(leaf_offset..leaf_offset + batch_len) {
+        Some(elements) => assert_recipients_match_list(recipients, elements),
+        None => panic!("Recipients run past the end of the leaf"),
+    }
+
+    if receipts.batch_process == Pubkey::default() {
+        receipts.batch_process = batch_process.key();
+        receipts.leaf = [get_j(cursor), get_k(cursor)];
+    }
+
+    let rent = Rent::get()?;
+    let mut failed = vec![];
+
+    for (i, account_info) in recipients.iter().enumerate() {
+        if receipts.is_paid(leaf_offset + i) {
+            msg!("Skipped {}, already paid", account_info.key());
+            continue;
+        }
+
+        // Simulated runs validate and advance like a real one, but only
+        // report what each recipient would get
+        let index = processed + i as u32;
+        if batch_process.simulate {
+            emit!(DistributionSimulated {
+                batch_process: batch_process.key(),
+                recipient: account_info.key(),
+                index,
+                amount: batch_process.amount,
+                payable: action != BatchAction::Transfer
+                    || can_receive_lamports(account_info, batch_process.amount, &rent),
+            });
+            continue;
+        }
+
+        // A failed CPI aborts the step, so only transfers can be checked
+        // up front and logged for a retry
+        let instruction = action.instruction(account_info.key(), batch_process.key(), vault.key());
+        match instruction {
+            None => {
+                if !can_receive_lamports(account_info, batch_process.amount, &rent) {
+                    if !failures.indices.contains(&index) {
+                        failed.push(index);
+                    }
+                    msg!("Failed to pay {}, logged index {} for retry", account_info.key(), index);
+                    continue;
+                }
+
+                transfer_from_vault(
+                    vault,
+                    account_info,
+                    system_program,
+                    failures.owner,
+                    vault_bump,
+                    batch_process.amount,
+                )?;
+
+                msg!("Payed {} {} Lamports", account_info.key(), batch_process.amount);
+            }
+            Some(instruction) => {
+                let mut account_infos = vec![
+                    account_info.clone(),
+                    batch_process.to_account_info(),
+                    vault.clone(),
+                ];
+                account_infos.extend_from_slice(action_accounts);
+
+                solana_program::program::invoke_signed(
+                    &instruction,
+                    &account_infos,
+                    &[
+                        &[
+                            b"batch_process",
+                            batch_process.id.as_ref(),
+                            batch_process.authority.as_ref(),
+                            batch_process.batch_id.to_le_bytes().as_ref(),
+                            &[batch_process_bump],
+                        ],
+                        &[b"vault", failures.owner.as_ref(), &[vault_bump]],
+                    ],
+                )?;
+
+                msg!("Invoked {} for {}", instruction.program_id, account_info.key());
+            }
+        }
+        receipts.mark_paid(leaf_offset + i);
+    }
+
+    if !failed.is_empty() {
+        realloc_with_rent(
+            &failures.to_account_info(),
+            payer,
+            system_program,
+            BatchFailures::size(failures.indices.len() + failed.len()),
+        )?;
+        failures.indices.append(&mut failed);
+    }
+
+    emit!(BatchStepProcessed {
+        batch_process: batch_process.key(),
+        shard: shard.map(|shard| shard.index),
+        start_index: processed,
+        count: batch_len as u32,
+        total_processed: processed + batch_len as u32,
+    });
+    Ok(batch_len as u32)
+}
+
+// Moves the cursor of an unsharded run past a distributed batch
+pub fn advance_batch_process(batch_process: &mut Account<BatchProcess>, count: u32) {
+    let next_total = batch_process.total_processed + count;
+    let next_position = get_current_indices(next_total);
+    batch_process.status = BatchProcessStatus::Processing;
+    batch_process.last_step_slot = Clock::get().unwrap().slot;
+    batch_process.total_processed = next_total;
+    batch_process.position = [next_position.0, next_position.1, next_position.2];
+    msg!("Batch Total: {}", next_total);
+
+    if next_total >= batch_process.total_elements {
+        complete_batch_process(batch_process);
+    }
+}
+
+// Moves the cursor of a shard past a distributed batch. The batch process
+// only learns about it with `sync_shards`, so shards never contend on it.
+pub fn advance_shard(shard: &mut Account<BatchShard>, count: u32) {
+    shard.status = BatchProcessStatus::Processing;
+    shard.last_step_slot = Clock::get().unwrap().slot;
+    shard.total_processed += count;
+    msg!("Shard {} Total: {}", shard.index, shard.total_processed);
+
+    if shard.total_processed >= shard.end {
+        shard.status = BatchProcessStatus::Completed;
+        msg!("Shard {} completed", shard.index);
+    }
+}
+
+pub fn complete_batch_process(batch_process: &mut Account<BatchProcess>) {
+    if batch_process.status == BatchProcessStatus::Completed {
+        return;
+    }
+    batch_process.status = BatchProcessStatus::Completed;
+    emit!(BatchCompleted {
+        batch_process: batch_process.key(),
+        total_processed: batch_process.total_processed,
+    });
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::state::IndexType;
+    use crate::state::{ActionAccount, IndexType};
     use anchor_lang::prelude::Pubkey;
 
     pub fn gen_big_list(total_elements: u32) -> BigList {
@@ -77,63 +462,148 @@ mod tests {
                 Pubkey::default(),
                 Pubkey::default(),
             ],
+            unique: false,
+            summaries: vec![],
+            capacity: 0,
+        }
+    }
+
+    #[test]
+    pub fn it_counts_batches_per_leaf() {
+        assert_eq!(get_batch_count(0, 12), 0);
+        assert_eq!(get_batch_count(12, 12), 1);
+        assert_eq!(get_batch_count(13, 12), 2);
+        // 256 = 21 * 12 + 4
+        assert_eq!(get_batch_count(256, 12), 22);
+        assert_eq!(get_batch_count(257, 12), 23);
+        assert_eq!(get_batch_count(10000, 12), 39 * 22 + 2);
+    }
+
+    #[test]
+    pub fn it_splits_shards_on_leaves() {
+        // 10000 elements fill 40 leaves
+        assert_eq!(get_shard_range(10000, 3, 0), (0, 13 * 256));
+        assert_eq!(get_shard_range(10000, 3, 1), (13 * 256, 26 * 256));
+        assert_eq!(get_shard_range(10000, 3, 2), (26 * 256, 10000));
+        assert_eq!(get_shard_range(512, 2, 1), (256, 512));
+        assert_eq!(get_shard_range(300, 1, 0), (0, 300));
+    }
+
+    #[test]
+    pub fn it_finds_children_from_summaries() {
+        let mut keys: Vec<Pubkey> = (0..6).map(|_| Pubkey::new_unique()).collect();
+        keys.sort();
+        assert_sorted_after(None, &keys);
+
+        let mut big_list = gen_big_list(6);
+        big_list.summaries = vec![ChildSummary::default()];
+        let mut big_list_j = gen_big_list(6);
+        for (k, chunk) in keys.chunks(2).enumerate() {
+            let mut leaf = gen_big_list(2);
+            leaf.elements = chunk.to_vec();
+            leaf.len = 2;
+            update_summaries(&mut big_list, &mut big_list_j, 0, k as u8, &leaf);
+        }
+
+        assert_eq!(big_list.find_child(&keys[4]), Some(0));
+        assert_eq!(big_list_j.find_child(&keys[0]), Some(0));
+        assert_eq!(big_list_j.find_child(&keys[3]), Some(1));
+        assert_eq!(big_list_j.find_child(&keys[5]), Some(2));
+        assert_eq!(big_list_j.find_child(&Pubkey::default()), None);
+
+        assert_eq!(big_list.summaries[0].count, 6);
+        assert_eq!(big_list.locate(5), Some((0, 5)));
+        assert_eq!(big_list_j.locate(3), Some((1, 1)));
+        assert_eq!(big_list_j.locate(6), None);
+    }
+
+    #[test]
+    pub fn it_summarizes_written_elements_only() {
+        let mut leaf = gen_big_list(0);
+        assert_eq!(ChildSummary::of_leaf(&leaf), ChildSummary::default());
+
+        let keys: Vec<Pubkey> = (0..2).map(|_| Pubkey::new_unique()).collect();
+        leaf.elements[..2].copy_from_slice(&keys);
+        leaf.len = 2;
+        let summary = ChildSummary::of_leaf(&leaf);
+        assert_eq!(summary.count, 2);
+        assert_eq!(summary.last, keys[1]);
+
+        let branch = ChildSummary::of_branch(&[summary, ChildSummary::default()]);
+        assert_eq!(branch.count, 2);
+        assert_eq!(branch.first, keys[0]);
+    }
+
+    fn invoke(program_id: Pubkey, accounts: Vec<ActionAccount>) -> BatchAction {
+        BatchAction::Invoke {
+            program_id,
+            data: vec![],
+            accounts,
         }
     }
 
+    fn fixed(is_signer: bool) -> ActionAccount {
+        ActionAccount {
+            source: ActionAccountSource::Fixed {
+                pubkey: Pubkey::new_unique(),
+            },
+            is_signer,
+            is_writable: false,
+        }
+    }
+
+    #[test]
+    pub fn it_accepts_actions_within_the_batch_size() {
+        assert_batch_action(&BatchAction::Transfer, MAX_BATCH_SIZE as u8);
+        // 2 fixed accounts and the program
+        let action = invoke(Pubkey::new_unique(), vec![fixed(false), fixed(false)]);
+        assert_batch_action(&action, MAX_BATCH_SIZE as u8 - 3);
+        let action = invoke(
+            Pubkey::new_unique(),
+            vec![ActionAccount {
+                source: ActionAccountSource::Vault,
+                is_signer: true,
+                is_writable: true,
+            }],
+        );
+        assert_batch_action(&action, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "leaves no room")]
+    pub fn it_rejects_actions_past_the_batch_size() {
+        let action = invoke(Pubkey::new_unique(), vec![fixed(false), fixed(false)]);
+        assert_batch_action(&action, MAX_BATCH_SIZE as u8 - 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "can not invoke the big list program")]
+    pub fn it_rejects_actions_invoking_the_program() {
+        assert_batch_action(&invoke(crate::ID, vec![]), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the batch process and the vault can sign")]
+    pub fn it_rejects_foreign_signers() {
+        assert_batch_action(&invoke(Pubkey::new_unique(), vec![fixed(true)]), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the batch process and the vault can sign")]
+    pub fn it_rejects_elements_as_signers() {
+        let element = ActionAccount {
+            source: ActionAccountSource::Element,
+            is_signer: true,
+            is_writable: true,
+        };
+        assert_batch_action(&invoke(Pubkey::new_unique(), vec![element]), 1);
+    }
+
     #[test]
-    pub fn it_returns_the_expected_indices() {
-        let total_elements = gen_big_list(256).total_elements;
-        let (j, k, l) = get_current_indices(total_elements);
-        assert_eq!(j, 0);
-        assert_eq!(k, 0);
-        assert_eq!(l, 255);
-
-        let total_elements = gen_big_list(257).total_elements;
-        let (j, k, l) = get_current_indices(total_elements);
-        assert_eq!(j, 0);
-        assert_eq!(k, 1);
-        assert_eq!(l, 0);
-
-        let total_elements = gen_big_list(255).total_elements;
-        let (j, k, l) = get_current_indices(total_elements);
-        assert_eq!(j, 0);
-        assert_eq!(k, 0);
-        assert_eq!(l, 254);
-
-        let total_elements = gen_big_list(10000).total_elements;
-        let (j, k, l) = get_current_indices(total_elements);
-        assert_eq!(j, 0);
-        assert_eq!(k, 39);
-        assert_eq!(l, 15);
-
-        // Max
-        let total_elements = gen_big_list(16777216).total_elements;
-        let (j, k, l) = get_current_indices(total_elements);
-        assert_eq!(j, 255);
-        assert_eq!(k, 255);
-        assert_eq!(l, 255);
-    }
-
-    pub fn it_returns_the_expected_k() {
-        let total_elements = gen_big_list(256).total_elements;
-        let k = get_k(total_elements);
-        assert_eq!(k, 0);
-
-        let total_elements = gen_big_list(257).total_elements;
-        let k = get_k(total_elements);
-        assert_eq!(k, 1);
-
-        let total_elements = gen_big_list(255).total_elements;
-        let k = get_k(total_elements);
-        assert_eq!(k, 0);
-
-        let total_elements = gen_big_list(10000).total_elements;
-        let k = get_k(total_elements);
-        assert_eq!(k, 39);
-
-        // Max
-        let total_elements = gen_big_list(16777216).total_elements;
-        let k = get_k(total_elements);
-        assert_eq!(k, 255);
+    #[should_panic]
+    pub fn it_rejects_unsorted_appends() {
+        let mut keys: Vec<Pubkey> = (0..2).map(|_| Pubkey::new_unique()).collect();
+        keys.sort();
+        assert_sorted_after(Some(keys[1]), &keys[..1]);
     }
 }
@@ -0,0 +1,455 @@
+#![cfg(feature = "clockwork")]
+
+mod common;
+
+use anchor_lang::{prelude::Pubkey, solana_program::system_program};
+use big_list::state::{
+    BatchFailures, BatchProcess, BatchProcessStatus, BatchShard, DistributionReceipts,
+};
+use clockwork_sdk::state::Thread;
+use common::*;
+use solana_sdk::{
+    account::AccountSharedData,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+
+const AMOUNT: u64 = 1_000_000_000;
+
+#[tokio::test]
+async fn it_distributes_in_steps() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    let addresses = fill(&mut context, 30).await;
+
+    send(
+        &mut context,
+        &[initialize_batch_process_ix(authority, 0, 12, AMOUNT, 0)],
+    )
+    .await
+    .unwrap();
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    let thread = Thread::pubkey(batch_process, 0.to_string());
+    let state: BatchProcess = fetch(&mut context, batch_process).await;
+    assert_eq!(state.status, BatchProcessStatus::Ready);
+
+    // 12 + 12 + 6
+    for step in 1..=3 {
+        assert!(exec_thread(&mut context, thread).await);
+        let state: BatchProcess = fetch(&mut context, batch_process).await;
+        assert_eq!(state.total_processed, (step * 12).min(30));
+    }
+
+    let state: BatchProcess = fetch(&mut context, batch_process).await;
+    assert_eq!(state.status, BatchProcessStatus::Completed);
+    assert!(!exec_thread(&mut context, thread).await);
+
+    for address in &addresses {
+        assert_eq!(lamports(&mut context, *address).await, AMOUNT);
+    }
+    let receipts: DistributionReceipts = fetch(
+        &mut context,
+        DistributionReceipts::pubkey(batch_process, 0, 0),
+    )
+    .await;
+    assert!((0..30).all(|index| receipts.is_paid(index)));
+}
+
+#[tokio::test]
+async fn it_distributes_across_leaves() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    // A full leaf and 44 elements in the next one
+    let addresses = fill(&mut context, 300).await;
+
+    let amount = 1_000_000;
+    send(
+        &mut context,
+        &[initialize_batch_process_ix(authority, 0, 12, amount, 0)],
+    )
+    .await
+    .unwrap();
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    let thread = Thread::pubkey(batch_process, 0.to_string());
+
+    // Batches stop at the end of a leaf, so one of them ends on 256
+    let mut processed = vec![];
+    while exec_thread(&mut context, thread).await {
+        let state: BatchProcess = fetch(&mut context, batch_process).await;
+        processed.push(state.total_processed);
+        assert!(processed.len() <= 30, "Batch never completed");
+    }
+    assert!(processed.contains(&256));
+    assert_eq!(processed.last(), Some(&300));
+
+    let state: BatchProcess = fetch(&mut context, batch_process).await;
+    assert_eq!(state.status, BatchProcessStatus::Completed);
+    for address in &addresses {
+        assert_eq!(lamports(&mut context, *address).await, amount);
+    }
+    for (k, len) in [(0, 256), (1, 44)] {
+        let receipts: DistributionReceipts = fetch(
+            &mut context,
+            DistributionReceipts::pubkey(batch_process, 0, k),
+        )
+        .await;
+        assert!((0..256).all(|index| receipts.is_paid(index) == (index < len)));
+    }
+}
+
+#[tokio::test]
+async fn it_logs_and_retries_failed_recipients() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+
+    // The middle recipient holds data its allocation can't make rent exempt
+    let amount = 1_000_000;
+    let addresses: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let stuck = addresses[1];
+    context.set_account(
+        &stuck,
+        &AccountSharedData::new(1, 1_000, &system_program::ID),
+    );
+    assert!(!rent.is_exempt(1 + amount, 1_000));
+    send(&mut context, &[append_ix(authority, 0, addresses.clone())])
+        .await
+        .unwrap();
+
+    send(
+        &mut context,
+        &[initialize_batch_process_ix(authority, 0, 12, amount, 0)],
+    )
+    .await
+    .unwrap();
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    let thread = Thread::pubkey(batch_process, 0.to_string());
+    assert!(exec_thread(&mut context, thread).await);
+
+    let state: BatchProcess = fetch(&mut context, batch_process).await;
+    assert_eq!(state.status, BatchProcessStatus::Completed);
+    let failures: BatchFailures = fetch(&mut context, BatchFailures::pubkey(batch_process)).await;
+    assert_eq!(failures.indices, vec![1]);
+    assert_eq!(lamports(&mut context, addresses[0]).await, amount);
+    assert_eq!(lamports(&mut context, stuck).await, 1);
+    assert_eq!(lamports(&mut context, addresses[2]).await, amount);
+
+    // Still stuck, the index stays logged
+    send(
+        &mut context,
+        &[retry_failed_ix(authority, 0, 0, 0, &[stuck])],
+    )
+    .await
+    .unwrap();
+    let failures: BatchFailures = fetch(&mut context, BatchFailures::pubkey(batch_process)).await;
+    assert_eq!(failures.indices, vec![1]);
+
+    // Topped up to rent exemption, the retry pays it once
+    let balance = rent.minimum_balance(1_000);
+    context.set_account(
+        &stuck,
+        &AccountSharedData::new(balance, 1_000, &system_program::ID),
+    );
+    context.warp_to_slot(100).unwrap();
+    send(
+        &mut context,
+        &[retry_failed_ix(authority, 0, 0, 0, &[stuck])],
+    )
+    .await
+    .unwrap();
+    assert_eq!(lamports(&mut context, stuck).await, balance + amount);
+    let failures: BatchFailures = fetch(&mut context, BatchFailures::pubkey(batch_process)).await;
+    assert!(failures.indices.is_empty());
+    let receipts: DistributionReceipts = fetch(
+        &mut context,
+        DistributionReceipts::pubkey(batch_process, 0, 0),
+    )
+    .await;
+    assert!((0..3).all(|index| receipts.is_paid(index)));
+
+    // Nothing is logged anymore, so a second retry has nobody to pay
+    context.warp_to_slot(200).unwrap();
+    assert!(send(
+        &mut context,
+        &[retry_failed_ix(authority, 0, 0, 0, &[stuck])],
+    )
+    .await
+    .is_err());
+    send(&mut context, &[retry_failed_ix(authority, 0, 0, 0, &[])])
+        .await
+        .unwrap();
+    assert_eq!(lamports(&mut context, stuck).await, balance + amount);
+}
+
+#[tokio::test]
+async fn it_reclaims_a_push_run_once_nothing_is_left_to_retry() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    let amount = 1_000_000;
+    let addresses: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let stuck = addresses[1];
+    context.set_account(
+        &stuck,
+        &AccountSharedData::new(1, 1_000, &system_program::ID),
+    );
+    send(&mut context, &[append_ix(authority, 0, addresses)])
+        .await
+        .unwrap();
+    send(
+        &mut context,
+        &[initialize_batch_process_ix(authority, 0, 12, amount, 0)],
+    )
+    .await
+    .unwrap();
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    let vault = BatchProcess::vault(batch_process);
+    let thread = Thread::pubkey(batch_process, 0.to_string());
+
+    // Running, then completed with a payment left to retry
+    assert!(send(&mut context, &[reclaim_ix(authority, 0)])
+        .await
+        .is_err());
+    assert!(exec_thread(&mut context, thread).await);
+    context.warp_to_slot(100).unwrap();
+    assert!(send(&mut context, &[reclaim_ix(authority, 0)])
+        .await
+        .is_err());
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let balance = rent.minimum_balance(1_000);
+    context.set_account(
+        &stuck,
+        &AccountSharedData::new(balance, 1_000, &system_program::ID),
+    );
+    send(
+        &mut context,
+        &[retry_failed_ix(authority, 0, 0, 0, &[stuck])],
+    )
+    .await
+    .unwrap();
+
+    // Only the vault's rent is left
+    let before = lamports(&mut context, authority).await;
+    let left = lamports(&mut context, vault).await;
+    assert!(left > 0);
+    send(&mut context, &[reclaim_ix(authority, 0)])
+        .await
+        .unwrap();
+    assert_eq!(lamports(&mut context, vault).await, 0);
+    // Less the fee of the reclaim
+    assert!(lamports(&mut context, authority).await > before + left - 10_000);
+}
+
+#[tokio::test]
+async fn it_pays_a_bounty_per_funded_batch() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    let addresses = fill(&mut context, 30).await;
+
+    let bounty = 10_000;
+    let amount = 1_000_000;
+    send(
+        &mut context,
+        &[initialize_crank_process_ix(
+            authority, 0, 12, amount, bounty, false,
+        )],
+    )
+    .await
+    .unwrap();
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    let vault = BatchProcess::vault(batch_process);
+    let state: BatchProcess = fetch(&mut context, batch_process).await;
+    assert_eq!(state.bounties, 3);
+
+    // 12 + 12 + 6, the keeper pays the receipts rent of the leaf
+    let keeper = Keypair::new();
+    send(
+        &mut context,
+        &[system_instruction::transfer(
+            &authority,
+            &keeper.pubkey(),
+            100_000_000,
+        )],
+    )
+    .await
+    .unwrap();
+    for (step, recipients) in addresses.chunks(12).enumerate() {
+        send_signed(
+            &mut context,
+            &[crank_distribute_ix(
+                authority,
+                0,
+                step as u32 * 12,
+                recipients,
+                keeper.pubkey(),
+            )],
+            &[&keeper],
+        )
+        .await
+        .unwrap();
+    }
+
+    let state: BatchProcess = fetch(&mut context, batch_process).await;
+    assert_eq!(state.status, BatchProcessStatus::Completed);
+    assert_eq!(state.bounties, 0);
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let receipts_rent = rent.minimum_balance(DistributionReceipts::LEN);
+    assert_eq!(
+        lamports(&mut context, keeper.pubkey()).await,
+        100_000_000 - receipts_rent + 3 * bounty
+    );
+    // Only the rent the vault was created with is left
+    assert_eq!(lamports(&mut context, vault).await, rent.minimum_balance(0));
+    for address in &addresses {
+        assert_eq!(lamports(&mut context, *address).await, amount);
+    }
+}
+
+#[tokio::test]
+async fn it_simulates_a_crank_run() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    let addresses = fill(&mut context, 12).await;
+
+    let bounty = 10_000;
+    send(
+        &mut context,
+        &[initialize_crank_process_ix(
+            authority, 0, 12, 1_000_000, bounty, true,
+        )],
+    )
+    .await
+    .unwrap();
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    let vault = BatchProcess::vault(batch_process);
+    let rent = context.banks_client.get_rent().await.unwrap();
+    // Only the bounty and the rent, nothing to pay out
+    assert_eq!(
+        lamports(&mut context, vault).await,
+        bounty + rent.minimum_balance(0)
+    );
+
+    let before = lamports(&mut context, addresses[0]).await;
+    send(
+        &mut context,
+        &[crank_distribute_ix(authority, 0, 0, &addresses, authority)],
+    )
+    .await
+    .unwrap();
+
+    let state: BatchProcess = fetch(&mut context, batch_process).await;
+    assert_eq!(state.status, BatchProcessStatus::Completed);
+    assert_eq!(lamports(&mut context, addresses[0]).await, before);
+    assert_eq!(lamports(&mut context, vault).await, rent.minimum_balance(0));
+}
+
+#[tokio::test]
+async fn it_distributes_shards_side_by_side() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    // 2 leaves, one per shard
+    let addresses = fill(&mut context, 300).await;
+
+    let amount = 1_000_000;
+    send(
+        &mut context,
+        &[initialize_batch_process_ix(authority, 0, 12, amount, 2)],
+    )
+    .await
+    .unwrap();
+    send(
+        &mut context,
+        &[
+            initialize_batch_shard_ix(authority, 0, 0),
+            initialize_batch_shard_ix(authority, 0, 1),
+        ],
+    )
+    .await
+    .unwrap();
+
+    // Each shard holds its own share, the batch vault only keeps its rent
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    let shards = [0, 1].map(|index| BatchShard::pubkey(batch_process, index));
+    assert_eq!(
+        lamports(&mut context, BatchProcess::vault(batch_process)).await,
+        rent.minimum_balance(0)
+    );
+    assert_eq!(
+        lamports(&mut context, BatchProcess::vault(shards[0])).await,
+        256 * amount + rent.minimum_balance(0)
+    );
+    assert_eq!(
+        lamports(&mut context, BatchProcess::vault(shards[1])).await,
+        44 * amount + rent.minimum_balance(0)
+    );
+
+    // Steps interleave and leave the batch process untouched
+    let before = context
+        .banks_client
+        .get_account(batch_process)
+        .await
+        .unwrap()
+        .unwrap();
+    let threads =
+        [0, 1].map(|index| Thread::pubkey(batch_process, BatchShard::thread_id(0, index)));
+    let mut running = [true, true];
+    while running.iter().any(|running| *running) {
+        for (index, thread) in threads.iter().enumerate() {
+            if running[index] {
+                running[index] = exec_thread(&mut context, *thread).await;
+            }
+        }
+    }
+    let after = context
+        .banks_client
+        .get_account(batch_process)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(before.data, after.data);
+
+    for (shard, end) in shards.iter().zip([256, 300]) {
+        let state: BatchShard = fetch(&mut context, *shard).await;
+        assert_eq!(state.status, BatchProcessStatus::Completed);
+        assert_eq!(state.total_processed, end);
+        assert_eq!(
+            lamports(&mut context, BatchProcess::vault(*shard)).await,
+            rent.minimum_balance(0)
+        );
+        let failures: BatchFailures = fetch(&mut context, BatchFailures::pubkey(*shard)).await;
+        assert_eq!(failures.owner, *shard);
+    }
+    for address in &addresses {
+        assert_eq!(lamports(&mut context, *address).await, amount);
+    }
+
+    send(&mut context, &[sync_shards_ix(authority, 0, 2)])
+        .await
+        .unwrap();
+    let state: BatchProcess = fetch(&mut context, batch_process).await;
+    assert_eq!(state.total_processed, 300);
+    assert_eq!(state.completed_shards, 2);
+    assert_eq!(state.status, BatchProcessStatus::Completed);
+}
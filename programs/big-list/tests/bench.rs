@@ -0,0 +1,596 @@
+#![cfg(feature = "clockwork")]
+
+// Compute units, transaction sizes and account sizes of the list and batch
+// instructions across input sizes. Natively built programs are not metered,
+// so the numbers only mean something against the BPF build:
+//
+//   anchor build && SBF_OUT_DIR=target/deploy cargo test -p big-list --test bench -- --ignored
+//
+// which rewrites bench/compute-units.md for review. Native runs still check
+// every case goes through and write their report under the target directory.
+// Either way it takes a while, so it only runs when asked for.
+
+mod common;
+
+use std::{fmt::Write, path::PathBuf};
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        system_program,
+    },
+};
+use big_list::{
+    constants::MAX_BATCH_SIZE,
+    state::{BatchFailures, BatchProcess, BatchShard, DistributionReceipts, IndexType, SetBucket},
+    utils::needs_rollover,
+};
+use clockwork_sdk::state::Thread;
+use common::*;
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    account::AccountSharedData, compute_budget::ComputeBudgetInstruction, packet::PACKET_DATA_SIZE,
+    signature::Signer, transaction::Transaction,
+};
+
+// Per instruction, unless a compute budget instruction raises it
+const DEFAULT_UNITS: u64 = 200_000;
+const MAX_UNITS: u32 = 1_400_000;
+// Addresses per write, like the client's appends
+const APPEND_CHUNK: usize = 25;
+
+struct Row {
+    instruction: &'static str,
+    input: String,
+    units: u64,
+    // Without the compute budget instruction
+    tx_bytes: usize,
+    accounts: Vec<(&'static str, usize)>,
+}
+
+// Runs `ix` under the highest compute limit and keeps what it consumed
+async fn measure(
+    context: &mut ProgramTestContext,
+    ix: Instruction,
+    instruction: &'static str,
+    input: String,
+) -> Row {
+    let payer = context.payer.pubkey();
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let message = Transaction::new_with_payer(std::slice::from_ref(&ix), Some(&payer)).message;
+    let tx_bytes =
+        1 + 64 * message.header.num_required_signatures as usize + message.serialize().len();
+
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_UNITS),
+            ix,
+        ],
+        Some(&payer),
+        &[&context.payer],
+        blockhash,
+    );
+    let simulation = context
+        .banks_client
+        .simulate_transaction(tx.clone())
+        .await
+        .unwrap();
+    let details = simulation.simulation_details.unwrap();
+    if let Some(Err(err)) = simulation.result {
+        panic!(
+            "{} {} failed: {}\n{:#?}",
+            instruction, input, err, details.logs
+        )
+    }
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    Row {
+        instruction,
+        input,
+        units: details.units_consumed,
+        tx_bytes,
+        accounts: vec![],
+    }
+}
+
+async fn size(context: &mut ProgramTestContext, pubkey: Pubkey) -> usize {
+    context
+        .banks_client
+        .get_account(pubkey)
+        .await
+        .unwrap()
+        .map_or(0, |account| account.data.len())
+}
+
+async fn bench_initialize() -> Row {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    let mut row = measure(
+        &mut context,
+        initialize_ix(authority),
+        "initialize",
+        "depth 3".to_string(),
+    )
+    .await;
+    row.accounts = vec![
+        ("root", size(&mut context, node(authority, &[])).await),
+        ("leaf", size(&mut context, node(authority, &[0, 0])).await),
+    ];
+    row
+}
+
+// Appends `count` addresses to a leaf already holding `prefilled`
+async fn bench_append(prefilled: u32, count: u32) -> Row {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    fill(&mut context, prefilled).await;
+
+    let addresses = (0..count).map(|_| Pubkey::new_unique()).collect();
+    let ix = append_ix(authority, prefilled, addresses);
+    let input = format!("{} onto {}", plural(count as usize, "address"), prefilled);
+    if !needs_rollover(prefilled, count as usize) {
+        let mut row = measure(&mut context, ix, "append", input).await;
+        let leaf = node(authority, &[0, 0]);
+        row.accounts = vec![("leaf", size(&mut context, leaf).await)];
+        return row;
+    }
+    let mut row = measure(&mut context, ix, "append_rollover_k", input).await;
+    row.accounts = vec![
+        ("branch", size(&mut context, node(authority, &[0])).await),
+        ("leaf", size(&mut context, node(authority, &[0, 0])).await),
+        (
+            "next leaf",
+            size(&mut context, node(authority, &[0, 1])).await,
+        ),
+    ];
+    row
+}
+
+// A prepaid list of `leaves` full leaves taken through every step
+async fn bench_prepaid(leaves: u8) -> Vec<Row> {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    let capacity = leaves as u32 * 256;
+    let mut rows = vec![];
+
+    let ix = initialize_with_capacity_ix(authority, capacity);
+    let mut row = measure(
+        &mut context,
+        ix,
+        "initialize_with_capacity",
+        plural(capacity as usize, "element"),
+    )
+    .await;
+    row.accounts = vec![("root", size(&mut context, node(authority, &[])).await)];
+    rows.push(row);
+
+    let ix = allocate_leaves_ix(authority, leaves);
+    let mut row = measure(&mut context, ix, "allocate_leaves", plural(leaves, "leaf")).await;
+    row.accounts = vec![
+        ("branch", size(&mut context, node(authority, &[0])).await),
+        ("leaf", size(&mut context, node(authority, &[0, 0])).await),
+    ];
+    rows.push(row);
+
+    for k in 0..leaves {
+        for start in (0..256).step_by(APPEND_CHUNK) {
+            let len = APPEND_CHUNK.min(256 - start);
+            let addresses = (0..len).map(|_| Pubkey::new_unique()).collect();
+            let start_index = k as u32 * 256 + start as u32;
+            let ix = write_range_ix(authority, start_index, addresses, false);
+            // Writes cost the same in every leaf, the first one is measured
+            if start_index > 0 {
+                send(&mut context, &[ix]).await.unwrap();
+                continue;
+            }
+            let mut row = measure(&mut context, ix, "write_range", plural(len, "address")).await;
+            row.accounts = vec![("leaf", size(&mut context, node(authority, &[0, 0])).await)];
+            rows.push(row);
+        }
+    }
+
+    let ix = sync_totals_ix(authority, leaves);
+    let mut row = measure(&mut context, ix, "sync_totals", plural(leaves, "leaf")).await;
+    row.accounts = vec![
+        ("root", size(&mut context, node(authority, &[])).await),
+        ("branch", size(&mut context, node(authority, &[0])).await),
+    ];
+    rows.push(row);
+    rows
+}
+
+// Creates a batch process over `recipients` elements and runs its first step
+async fn bench_batch(recipients: u8) -> Vec<Row> {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    fill(&mut context, recipients as u32).await;
+
+    let ix = initialize_batch_process_ix(authority, 0, recipients, 1_000_000, 0);
+    let input = plural(recipients, "recipient");
+    let mut setup = measure(&mut context, ix, "initialize_batch_process", input.clone()).await;
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    let failures = BatchFailures::pubkey(batch_process);
+    setup.accounts = vec![
+        ("batch process", size(&mut context, batch_process).await),
+        ("failures", size(&mut context, failures).await),
+    ];
+
+    let thread = Thread::pubkey(batch_process, 0.to_string());
+    let ix = exec_thread_ix(&mut context, thread).await.unwrap();
+    let mut step = measure(&mut context, ix, "batch_distribute", input).await;
+    // Receipts are created by the first step of each leaf
+    let receipts = DistributionReceipts::pubkey(batch_process, 0, 0);
+    step.accounts = vec![("receipts", size(&mut context, receipts).await)];
+    vec![setup, step]
+}
+
+// The steps past a full leaf, one that only loads the next leaf and the
+// first one paying from it, which creates its receipts
+async fn bench_next_leaf(recipients: u8) -> Vec<Row> {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    fill(&mut context, 256 + recipients as u32).await;
+
+    send(
+        &mut context,
+        &[initialize_batch_process_ix(
+            authority, 0, recipients, 1_000_000, 0,
+        )],
+    )
+    .await
+    .unwrap();
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    let thread = Thread::pubkey(batch_process, 0.to_string());
+    while fetch::<BatchProcess>(&mut context, batch_process)
+        .await
+        .total_processed
+        < 256
+    {
+        assert!(exec_thread(&mut context, thread).await);
+    }
+
+    let ix = exec_thread_ix(&mut context, thread).await.unwrap();
+    let load = measure(
+        &mut context,
+        ix,
+        "batch_distribute",
+        "next leaf, no recipients".to_string(),
+    )
+    .await;
+    let ix = exec_thread_ix(&mut context, thread).await.unwrap();
+    let input = format!("{}, next leaf", plural(recipients, "recipient"));
+    let mut step = measure(&mut context, ix, "batch_distribute", input).await;
+    let receipts = DistributionReceipts::pubkey(batch_process, 0, 1);
+    step.accounts = vec![("receipts", size(&mut context, receipts).await)];
+    vec![load, step]
+}
+
+// Appends `count` addresses to a unique list, spread over `buckets` buckets
+async fn bench_unique_append(count: u32, buckets: u8) -> Row {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(
+        &mut context,
+        &[initialize_list_ix(
+            authority,
+            true,
+            IndexType::GrowableIndex,
+        )],
+    )
+    .await
+    .unwrap();
+    for prefix in 0..buckets {
+        send(&mut context, &[initialize_bucket_ix(authority, prefix)])
+            .await
+            .unwrap();
+    }
+
+    let addresses = (0..count)
+        .map(|i| {
+            let mut bytes = Pubkey::new_unique().to_bytes();
+            bytes[0] = (i % buckets as u32) as u8;
+            Pubkey::new_from_array(bytes)
+        })
+        .collect();
+    let mut ix = append_ix(authority, 0, addresses);
+    let list = node(authority, &[]);
+    ix.accounts.extend(
+        (0..buckets).map(|prefix| AccountMeta::new(SetBucket::pubkey(list, prefix), false)),
+    );
+    let input = format!(
+        "{}, {}",
+        plural(count as usize, "address"),
+        plural(buckets, "bucket")
+    );
+    let mut row = measure(&mut context, ix, "append", input).await;
+    row.accounts = vec![(
+        "bucket",
+        size(&mut context, SetBucket::pubkey(list, 0)).await,
+    )];
+    row
+}
+
+// A claim process over a list the authority is the first element of, one
+// claim and the reclaim once it expired
+async fn bench_claims() -> Vec<Row> {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    let mut addresses = vec![authority];
+    addresses.extend((0..24).map(|_| Pubkey::new_unique()));
+    send(&mut context, &[append_ix(authority, 0, addresses)])
+        .await
+        .unwrap();
+
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let expires_at = clock.unix_timestamp + 3600;
+    let ix = initialize_claim_ix(authority, 0, 1_000_000, expires_at);
+    let mut setup = measure(
+        &mut context,
+        ix,
+        "initialize_claim",
+        plural(25u8, "element"),
+    )
+    .await;
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    setup.accounts = vec![("batch process", size(&mut context, batch_process).await)];
+
+    let ix = claim_ix(authority, 0, 0, authority);
+    let mut claim = measure(&mut context, ix, "claim", "first of a leaf".to_string()).await;
+    let receipts = DistributionReceipts::pubkey(batch_process, 0, 0);
+    claim.accounts = vec![("receipts", size(&mut context, receipts).await)];
+
+    set_unix_timestamp(&mut context, expires_at).await;
+    let ix = reclaim_ix(authority, 0);
+    let reclaim = measure(&mut context, ix, "reclaim", "expired claims".to_string()).await;
+    vec![setup, claim, reclaim]
+}
+
+// A crank process and its first step, paid to the authority as keeper
+async fn bench_crank(recipients: u8) -> Vec<Row> {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    let addresses = fill(&mut context, recipients as u32).await;
+
+    let ix = initialize_crank_process_ix(authority, 0, recipients, 1_000_000, 10_000, false);
+    let input = plural(recipients, "recipient");
+    let mut setup = measure(&mut context, ix, "initialize_crank_process", input.clone()).await;
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    setup.accounts = vec![("batch process", size(&mut context, batch_process).await)];
+
+    let ix = crank_distribute_ix(authority, 0, 0, &addresses, authority);
+    let mut step = measure(&mut context, ix, "crank_distribute", input).await;
+    let receipts = DistributionReceipts::pubkey(batch_process, 0, 0);
+    step.accounts = vec![("receipts", size(&mut context, receipts).await)];
+    vec![setup, step]
+}
+
+// Two shards over two leaves, the steps of the first one and the sync
+async fn bench_shards(recipients: u8) -> Vec<Row> {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    fill(&mut context, 256 + recipients as u32).await;
+    send(
+        &mut context,
+        &[initialize_batch_process_ix(
+            authority, 0, recipients, 1_000_000, 2,
+        )],
+    )
+    .await
+    .unwrap();
+
+    let ix = initialize_batch_shard_ix(authority, 0, 0);
+    let mut setup = measure(
+        &mut context,
+        ix,
+        "initialize_batch_shard",
+        "shard 0".to_string(),
+    )
+    .await;
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    let shard = BatchShard::pubkey(batch_process, 0);
+    setup.accounts = vec![
+        ("shard", size(&mut context, shard).await),
+        (
+            "failures",
+            size(&mut context, BatchFailures::pubkey(shard)).await,
+        ),
+    ];
+    send(&mut context, &[initialize_batch_shard_ix(authority, 0, 1)])
+        .await
+        .unwrap();
+
+    // The first step only loads the shard's first leaf
+    let thread = Thread::pubkey(batch_process, BatchShard::thread_id(0, 0));
+    let ix = exec_thread_ix(&mut context, thread).await.unwrap();
+    let load = measure(
+        &mut context,
+        ix,
+        "shard_distribute",
+        "first leaf, no recipients".to_string(),
+    )
+    .await;
+    let ix = exec_thread_ix(&mut context, thread).await.unwrap();
+    let input = plural(recipients, "recipient");
+    let mut step = measure(&mut context, ix, "shard_distribute", input).await;
+    let receipts = DistributionReceipts::pubkey(batch_process, 0, 0);
+    step.accounts = vec![("receipts", size(&mut context, receipts).await)];
+
+    let ix = sync_shards_ix(authority, 0, 2);
+    let sync = measure(&mut context, ix, "sync_shards", plural(2u8, "shard")).await;
+    vec![setup, load, step, sync]
+}
+
+// A push run where every recipient failed, retried once they can be paid
+async fn bench_retry(recipients: u8) -> Row {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    // Accounts holding data the allocation can't make rent exempt
+    let addresses: Vec<Pubkey> = (0..recipients).map(|_| Pubkey::new_unique()).collect();
+    for address in &addresses {
+        context.set_account(
+            address,
+            &AccountSharedData::new(1, 1_000, &system_program::ID),
+        );
+    }
+    for (i, chunk) in addresses.chunks(25).enumerate() {
+        send(
+            &mut context,
+            &[append_ix(authority, i as u32 * 25, chunk.to_vec())],
+        )
+        .await
+        .unwrap();
+    }
+    send(
+        &mut context,
+        &[initialize_batch_process_ix(
+            authority, 0, recipients, 1_000_000, 0,
+        )],
+    )
+    .await
+    .unwrap();
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    let thread = Thread::pubkey(batch_process, 0.to_string());
+    assert!(exec_thread(&mut context, thread).await);
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    for address in &addresses {
+        let account =
+            AccountSharedData::new(rent.minimum_balance(1_000), 1_000, &system_program::ID);
+        context.set_account(address, &account);
+    }
+    let ix = retry_failed_ix(authority, 0, 0, 0, &addresses);
+    let mut row = measure(
+        &mut context,
+        ix,
+        "retry_failed",
+        plural(recipients, "recipient"),
+    )
+    .await;
+    let failures = BatchFailures::pubkey(batch_process);
+    row.accounts = vec![("failures", size(&mut context, failures).await)];
+    row
+}
+
+fn plural(n: impl Into<usize>, noun: &str) -> String {
+    match (n.into(), noun) {
+        (1, _) => format!("1 {}", noun),
+        (n, "leaf") => format!("{} leaves", n),
+        (n, "address") => format!("{} addresses", n),
+        (n, _) => format!("{} {}s", n, noun),
+    }
+}
+
+fn report(rows: &[Row], bpf: bool) -> String {
+    let mut report = String::from("# Compute units\n\n");
+    if bpf {
+        report.push_str("Measured against the BPF build with `tests/bench.rs`.");
+    } else {
+        report.push_str("Native run, compute units are not metered.");
+    }
+    writeln!(
+        report,
+        " Instructions get {} units by default and {} at most. Transactions \
+         marked * go over the {} byte packet limit, `batch_distribute` and \
+         `shard_distribute` run through the thread program.\n",
+        DEFAULT_UNITS, MAX_UNITS, PACKET_DATA_SIZE
+    )
+    .unwrap();
+    report.push_str("| Instruction | Input | Compute units | % of default | Transaction bytes | Account bytes |\n");
+    report.push_str("| --- | --- | ---: | ---: | ---: | --- |\n");
+    for row in rows {
+        let accounts: Vec<String> = row
+            .accounts
+            .iter()
+            .map(|(name, bytes)| format!("{} {}", name, bytes))
+            .collect();
+        let (units, share) = if bpf {
+            (
+                row.units.to_string(),
+                (row.units * 100 / DEFAULT_UNITS).to_string(),
+            )
+        } else {
+            ("n/a".to_string(), "n/a".to_string())
+        };
+        writeln!(
+            report,
+            "| {} | {} | {} | {} | {}{} | {} |",
+            row.instruction,
+            row.input,
+            units,
+            share,
+            row.tx_bytes,
+            if row.tx_bytes > PACKET_DATA_SIZE {
+                "*"
+            } else {
+                ""
+            },
+            accounts.join(", "),
+        )
+        .unwrap();
+    }
+    report
+}
+
+#[tokio::test]
+#[ignore = "benchmark, run with --ignored"]
+async fn it_reports_compute_units() {
+    let mut rows = vec![bench_initialize().await];
+    for (prefilled, count) in [(0, 1), (0, 25), (0, 128), (0, 256), (231, 25)] {
+        rows.push(bench_append(prefilled, count).await);
+    }
+    for (prefilled, count) in [(250, 10), (231, 50), (256, 256)] {
+        rows.push(bench_append(prefilled, count).await);
+    }
+    for leaves in [1, 4] {
+        rows.extend(bench_prepaid(leaves).await);
+    }
+    for recipients in [1, 8, MAX_BATCH_SIZE as u8] {
+        rows.extend(bench_batch(recipients).await);
+    }
+    rows.extend(bench_next_leaf(MAX_BATCH_SIZE as u8).await);
+    for recipients in [1, MAX_BATCH_SIZE as u8] {
+        rows.extend(bench_crank(recipients).await);
+    }
+    rows.extend(bench_shards(MAX_BATCH_SIZE as u8).await);
+    for recipients in [1, MAX_BATCH_SIZE as u8] {
+        rows.push(bench_retry(recipients).await);
+    }
+    rows.extend(bench_claims().await);
+    for (count, buckets) in [(1, 1), (25, 1), (25, 25)] {
+        rows.push(bench_unique_append(count, buckets).await);
+    }
+
+    // `test-sbf` and `SBF_OUT_DIR` load the BPF build, see `ProgramTest::new`
+    let bpf = std::env::var("SBF_OUT_DIR").is_ok() || std::env::var("BPF_OUT_DIR").is_ok();
+    let path = if bpf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("bench")
+    } else {
+        PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+    };
+    std::fs::create_dir_all(&path).unwrap();
+    let report = report(&rows, bpf);
+    std::fs::write(path.join("compute-units.md"), &report).unwrap();
+    println!("{}", report);
+}
@@ -0,0 +1,164 @@
+#![cfg(feature = "clockwork")]
+
+mod common;
+
+use anchor_lang::prelude::{Clock, Pubkey};
+use big_list::state::{BatchProcess, BatchProcessStatus};
+use common::*;
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+
+const AMOUNT: u64 = 1_000_000;
+
+// A list of 3 claimants, funded so they can pay for the receipts, with
+// claims open for an hour
+async fn setup(context: &mut ProgramTestContext) -> (Vec<Keypair>, i64) {
+    let authority = context.payer.pubkey();
+    send(context, &[initialize_ix(authority)]).await.unwrap();
+
+    let claimants: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+    let addresses: Vec<Pubkey> = claimants.iter().map(|c| c.pubkey()).collect();
+    let mut ixs = vec![append_ix(authority, 0, addresses.clone())];
+    for address in &addresses {
+        ixs.push(system_instruction::transfer(
+            &authority,
+            address,
+            100_000_000,
+        ));
+    }
+    send(context, &ixs).await.unwrap();
+
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let expires_at = clock.unix_timestamp + 3600;
+    send(
+        context,
+        &[initialize_claim_ix(authority, 0, AMOUNT, expires_at)],
+    )
+    .await
+    .unwrap();
+    (claimants, expires_at)
+}
+
+#[tokio::test]
+async fn it_claims_once() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    let (claimants, _) = setup(&mut context).await;
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    let vault = BatchProcess::vault(batch_process);
+
+    let before = lamports(&mut context, vault).await;
+    let claimant = &claimants[1];
+    send_signed(
+        &mut context,
+        &[claim_ix(authority, 0, 1, claimant.pubkey())],
+        &[claimant],
+    )
+    .await
+    .unwrap();
+    assert_eq!(lamports(&mut context, vault).await, before - AMOUNT);
+
+    // Warping gives the retry a fresh blockhash so it isn't deduplicated
+    context.warp_to_slot(100).unwrap();
+    assert!(send_signed(
+        &mut context,
+        &[claim_ix(authority, 0, 1, claimant.pubkey())],
+        &[claimant],
+    )
+    .await
+    .is_err());
+    assert_eq!(lamports(&mut context, vault).await, before - AMOUNT);
+
+    // Nobody can claim someone else's index
+    let other = &claimants[2];
+    assert!(send_signed(
+        &mut context,
+        &[claim_ix(authority, 0, 0, other.pubkey())],
+        &[other],
+    )
+    .await
+    .is_err());
+
+    let state: BatchProcess = fetch(&mut context, batch_process).await;
+    assert_eq!(state.total_processed, 1);
+}
+
+#[tokio::test]
+async fn it_rejects_claims_after_expiry() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    let (claimants, expires_at) = setup(&mut context).await;
+
+    set_unix_timestamp(&mut context, expires_at).await;
+    let claimant = &claimants[0];
+    assert!(send_signed(
+        &mut context,
+        &[claim_ix(authority, 0, 0, claimant.pubkey())],
+        &[claimant],
+    )
+    .await
+    .is_err());
+}
+
+#[tokio::test]
+async fn it_reclaims_only_after_expiry() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    let (claimants, expires_at) = setup(&mut context).await;
+    let batch_process = BatchProcess::pubkey(authority, ID, 0);
+    let vault = BatchProcess::vault(batch_process);
+
+    let claimant = &claimants[0];
+    send_signed(
+        &mut context,
+        &[claim_ix(authority, 0, 0, claimant.pubkey())],
+        &[claimant],
+    )
+    .await
+    .unwrap();
+    assert!(send(&mut context, &[reclaim_ix(authority, 0)])
+        .await
+        .is_err());
+
+    // Warping gives the retry a fresh blockhash so it isn't deduplicated
+    context.warp_to_slot(100).unwrap();
+    set_unix_timestamp(&mut context, expires_at).await;
+    let before = lamports(&mut context, authority).await;
+    let unclaimed = lamports(&mut context, vault).await;
+    send(&mut context, &[reclaim_ix(authority, 0)])
+        .await
+        .unwrap();
+    assert_eq!(lamports(&mut context, vault).await, 0);
+    // Less the fee of the reclaim
+    assert!(lamports(&mut context, authority).await > before + unclaimed - 10_000);
+    let state: BatchProcess = fetch(&mut context, batch_process).await;
+    assert_eq!(state.status, BatchProcessStatus::Completed);
+}
+
+#[tokio::test]
+async fn it_only_funds_the_elements_at_the_start() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    setup(&mut context).await;
+
+    let late = Keypair::new();
+    send(
+        &mut context,
+        &[
+            append_ix(authority, 3, vec![late.pubkey()]),
+            system_instruction::transfer(&authority, &late.pubkey(), 100_000_000),
+        ],
+    )
+    .await
+    .unwrap();
+    assert!(send_signed(
+        &mut context,
+        &[claim_ix(authority, 0, 3, late.pubkey())],
+        &[&late],
+    )
+    .await
+    .is_err());
+}
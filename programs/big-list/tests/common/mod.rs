@@ -0,0 +1,718 @@
+#![allow(dead_code)]
+
+use std::{
+    panic::AssertUnwindSafe,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        entrypoint::ProgramResult,
+        instruction::Instruction,
+        program::{get_return_data, invoke_signed},
+        system_instruction, system_program,
+    },
+    AccountDeserialize, AccountSerialize, InstructionData, ToAccountMetas,
+};
+use big_list::{
+    accounts, instruction,
+    state::{
+        BatchAction, BatchFailures, BatchProcess, BatchShard, BatchTrigger, BigList,
+        DistributionReceipts, IndexType, SetBucket,
+    },
+    utils::{get_j, get_k, needs_rollover},
+};
+use clockwork_sdk::{
+    state::{InstructionData as ThreadInstruction, Thread, ThreadResponse},
+    utils::{anchor_sighash, PAYER_PUBKEY},
+};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::AccountSharedData,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+pub const ID: &str = "my_big_list";
+
+static EXEC_NONCE: AtomicU64 = AtomicU64::new(0);
+
+// Space the stand-in thread keeps for its next instruction
+const THREAD_SPACE: u64 = 4096;
+
+// What the stand-in thread program stores in a thread account
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct MockThread {
+    pub authority: Pubkey,
+    pub id: String,
+    pub next: Option<ThreadInstruction>,
+}
+
+// Stands in for the Clockwork thread program. `thread_create` keeps the
+// kickoff instruction in the thread account, any other instruction runs
+// the stored one signed by the thread, like a worker would, and keeps the
+// next instruction it returns.
+fn mock_thread_program(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    if data[..8] == anchor_sighash("thread_create") {
+        // [authority, payer, system_program, thread]
+        let (id, kickoff): (String, ThreadInstruction) =
+            AnchorDeserialize::deserialize(&mut &data[8..])?;
+        let authority = accounts[0].key;
+        let thread = &accounts[3];
+        let (_, bump) = Pubkey::find_program_address(
+            &[b"thread", authority.as_ref(), id.as_bytes()],
+            program_id,
+        );
+        let seeds: &[&[u8]] = &[b"thread", authority.as_ref(), id.as_bytes(), &[bump]];
+
+        // The thread was funded before creation, so allocate and assign it
+        // rather than `create_account`
+        invoke_signed(
+            &system_instruction::allocate(thread.key, THREAD_SPACE),
+            accounts,
+            &[seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(thread.key, program_id),
+            accounts,
+            &[seeds],
+        )?;
+        let state = MockThread {
+            authority: *authority,
+            id,
+            next: Some(kickoff),
+        };
+        state.serialize(&mut &mut thread.data.borrow_mut()[..])?;
+        return Ok(());
+    }
+
+    // [thread, payer, ...accounts of the stored instruction]
+    let thread = &accounts[0];
+    let payer = &accounts[1];
+    let mut state = MockThread::deserialize(&mut &thread.data.borrow()[..])?;
+    let next = state.next.take().expect("Thread has nothing to run");
+    let ix = Instruction {
+        program_id: next.program_id,
+        accounts: next
+            .accounts
+            .iter()
+            .map(|meta| {
+                let pubkey = if meta.pubkey == PAYER_PUBKEY {
+                    *payer.key
+                } else {
+                    meta.pubkey
+                };
+                AccountMeta {
+                    pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                }
+            })
+            .collect(),
+        data: next.data,
+    };
+    let (_, bump) = Pubkey::find_program_address(
+        &[b"thread", state.authority.as_ref(), state.id.as_bytes()],
+        program_id,
+    );
+    invoke_signed(
+        &ix,
+        accounts,
+        &[&[
+            b"thread",
+            state.authority.as_ref(),
+            state.id.as_bytes(),
+            &[bump],
+        ]],
+    )?;
+
+    if let Some((_, data)) = get_return_data() {
+        let response = ThreadResponse::deserialize(&mut data.as_slice())?;
+        state.next = response.next_instruction;
+    }
+    state.serialize(&mut &mut thread.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Programs abort on panics, natively a panic would take the test bank down
+// with it, so it fails the transaction instead
+fn entry(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    std::panic::catch_unwind(AssertUnwindSafe(|| {
+        big_list::entry(program_id, accounts, data)
+    }))
+    .unwrap_or(Err(ProgramError::Custom(u32::MAX)))
+}
+
+pub async fn start() -> ProgramTestContext {
+    let mut test = ProgramTest::new("big_list", big_list::ID, processor!(entry));
+    // Always native, also when `SBF_OUT_DIR` loads big_list from its BPF build
+    test.add_builtin_program(
+        "clockwork_thread_program",
+        clockwork_sdk::ID,
+        processor!(mock_thread_program).unwrap(),
+    );
+    test.start_with_context().await
+}
+
+pub async fn send(
+    context: &mut ProgramTestContext,
+    ixs: &[Instruction],
+) -> std::result::Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        ixs,
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        blockhash,
+    );
+    context.banks_client.process_transaction(tx).await
+}
+
+// Like `send`, with more signers than the payer
+pub async fn send_signed(
+    context: &mut ProgramTestContext,
+    ixs: &[Instruction],
+    signers: &[&Keypair],
+) -> std::result::Result<(), BanksClientError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(
+        ixs,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(tx).await
+}
+
+pub async fn fetch<T: AccountDeserialize>(context: &mut ProgramTestContext, pubkey: Pubkey) -> T {
+    let account = context
+        .banks_client
+        .get_account(pubkey)
+        .await
+        .unwrap()
+        .unwrap();
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+// Writes `state` straight into `pubkey`, for lists too long to build up in a test
+pub async fn store<T: AccountSerialize>(
+    context: &mut ProgramTestContext,
+    pubkey: Pubkey,
+    state: &T,
+) {
+    let mut data = vec![];
+    state.try_serialize(&mut data).unwrap();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let mut account =
+        AccountSharedData::new(rent.minimum_balance(data.len()), data.len(), &big_list::ID);
+    account.set_data(data);
+    context.set_account(&pubkey, &account);
+}
+
+pub async fn lamports(context: &mut ProgramTestContext, pubkey: Pubkey) -> u64 {
+    context.banks_client.get_balance(pubkey).await.unwrap()
+}
+
+pub fn node(authority: Pubkey, path: &[u8]) -> Pubkey {
+    BigList::pubkey(authority, ID, path)
+}
+
+pub fn initialize_ix(authority: Pubkey) -> Instruction {
+    initialize_list_ix(authority, false, IndexType::GrowableIndex)
+}
+
+pub fn initialize_list_ix(authority: Pubkey, unique: bool, index_type: IndexType) -> Instruction {
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::InitializeBigList {
+            big_list: node(authority, &[]),
+            big_list_j: node(authority, &[0]),
+            big_list_k: node(authority, &[0, 0]),
+            authority,
+            signer: authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Initialize {
+            id: ID.to_string(),
+            depth: 3,
+            unique,
+            index_type,
+        }
+        .data(),
+    }
+}
+
+pub fn initialize_bucket_ix(authority: Pubkey, prefix: u8) -> Instruction {
+    let big_list = node(authority, &[]);
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::InitializeBucket {
+            big_list,
+            bucket: SetBucket::pubkey(big_list, prefix),
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeBucket {
+            id: ID.to_string(),
+            prefix,
+        }
+        .data(),
+    }
+}
+
+pub fn initialize_with_capacity_ix(authority: Pubkey, capacity: u32) -> Instruction {
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::InitializeWithCapacity {
+            big_list: node(authority, &[]),
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeWithCapacity {
+            id: ID.to_string(),
+            capacity,
+        }
+        .data(),
+    }
+}
+
+pub fn allocate_leaves_ix(authority: Pubkey, count: u8) -> Instruction {
+    let mut metas = accounts::AllocateLeaves {
+        big_list: node(authority, &[]),
+        big_list_j: node(authority, &[0]),
+        authority,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    metas.extend((0..count).map(|k| AccountMeta::new(node(authority, &[0, k]), false)));
+    Instruction {
+        program_id: big_list::ID,
+        accounts: metas,
+        data: instruction::AllocateLeaves {
+            id: ID.to_string(),
+            j: 0,
+            k_start: 0,
+            count,
+        }
+        .data(),
+    }
+}
+
+pub fn write_range_ix(
+    authority: Pubkey,
+    start_index: u32,
+    addresses: Vec<Pubkey>,
+    overwrite: bool,
+) -> Instruction {
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::WriteRange {
+            big_list: node(authority, &[]),
+            big_list_k: node(authority, &[0, (start_index / 256) as u8]),
+            authority,
+        }
+        .to_account_metas(None),
+        data: instruction::WriteRange {
+            id: ID.to_string(),
+            start_index,
+            addresses,
+            overwrite,
+        }
+        .data(),
+    }
+}
+
+pub fn sync_totals_ix(authority: Pubkey, count: u8) -> Instruction {
+    let mut metas = accounts::SyncTotals {
+        big_list: node(authority, &[]),
+        big_list_j: node(authority, &[0]),
+        authority,
+        payer: authority,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    metas.extend((0..count).map(|k| AccountMeta::new_readonly(node(authority, &[0, k]), false)));
+    Instruction {
+        program_id: big_list::ID,
+        accounts: metas,
+        data: instruction::SyncTotals {
+            id: ID.to_string(),
+            j: 0,
+            k_start: 0,
+        }
+        .data(),
+    }
+}
+
+// `append` while the addresses fit in the current leaf, `append_rollover_k` otherwise
+pub fn append_ix(authority: Pubkey, total: u32, addresses: Vec<Pubkey>) -> Instruction {
+    let (j, k) = (get_j(total), get_k(total));
+    let next_total = total + addresses.len() as u32;
+    if !needs_rollover(total, addresses.len()) {
+        return Instruction {
+            program_id: big_list::ID,
+            accounts: accounts::Append {
+                big_list: node(authority, &[]),
+                big_list_j: node(authority, &[j]),
+                big_list_k: node(authority, &[j, k]),
+                authority,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::Append {
+                id: ID.to_string(),
+                addresses,
+            }
+            .data(),
+        };
+    }
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::AppendRolloverK {
+            big_list: node(authority, &[]),
+            big_list_j: node(authority, &[j]),
+            big_list_k: node(authority, &[j, k]),
+            big_list_k_next: node(authority, &[get_j(next_total), get_k(next_total)]),
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::AppendRolloverK {
+            id: ID.to_string(),
+            addresses,
+        }
+        .data(),
+    }
+}
+
+// Appends `count` fresh addresses 25 at a time
+pub async fn fill(context: &mut ProgramTestContext, count: u32) -> Vec<Pubkey> {
+    let authority = context.payer.pubkey();
+    let addresses: Vec<Pubkey> = (0..count).map(|_| Pubkey::new_unique()).collect();
+    let mut total = fetch::<BigList>(context, node(authority, &[]))
+        .await
+        .total_elements;
+    for chunk in addresses.chunks(25) {
+        send(context, &[append_ix(authority, total, chunk.to_vec())])
+            .await
+            .unwrap();
+        total += chunk.len() as u32;
+    }
+    addresses
+}
+
+pub fn initialize_batch_process_ix(
+    authority: Pubkey,
+    batch_id: u64,
+    batch_size: u8,
+    amount: u64,
+    shards: u8,
+) -> Instruction {
+    let batch_process = BatchProcess::pubkey(authority, ID, batch_id);
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::InitializeBatchProcess {
+            batch_process,
+            big_list: node(authority, &[]),
+            big_list_j: node(authority, &[0]),
+            big_list_k: node(authority, &[0, 0]),
+            failures: BatchFailures::pubkey(batch_process),
+            vault: BatchProcess::vault(batch_process),
+            authority,
+            batch_processor_thread: Thread::pubkey(batch_process, batch_id.to_string()),
+            thread_program: clockwork_sdk::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeBatchProcess {
+            id: ID.to_string(),
+            batch_id,
+            batch_size,
+            trigger: BatchTrigger::Immediate,
+            amount,
+            action: BatchAction::Transfer,
+            shards,
+            simulate: false,
+        }
+        .data(),
+    }
+}
+
+pub fn initialize_batch_shard_ix(authority: Pubkey, batch_id: u64, index: u8) -> Instruction {
+    let batch_process = BatchProcess::pubkey(authority, ID, batch_id);
+    let shard = BatchShard::pubkey(batch_process, index);
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::InitializeBatchShard {
+            batch_process,
+            shard,
+            failures: BatchFailures::pubkey(shard),
+            vault: BatchProcess::vault(batch_process),
+            shard_vault: BatchProcess::vault(shard),
+            big_list: node(authority, &[]),
+            authority,
+            shard_thread: Thread::pubkey(batch_process, BatchShard::thread_id(batch_id, index)),
+            thread_program: clockwork_sdk::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeBatchShard { index }.data(),
+    }
+}
+
+pub fn sync_shards_ix(authority: Pubkey, batch_id: u64, shards: u8) -> Instruction {
+    let batch_process = BatchProcess::pubkey(authority, ID, batch_id);
+    let mut accounts = accounts::SyncShards { batch_process }.to_account_metas(None);
+    accounts.extend(
+        (0..shards).map(|index| {
+            AccountMeta::new_readonly(BatchShard::pubkey(batch_process, index), false)
+        }),
+    );
+    Instruction {
+        program_id: big_list::ID,
+        accounts,
+        data: instruction::SyncShards {}.data(),
+    }
+}
+
+pub fn initialize_crank_process_ix(
+    authority: Pubkey,
+    batch_id: u64,
+    batch_size: u8,
+    amount: u64,
+    bounty: u64,
+    simulate: bool,
+) -> Instruction {
+    let batch_process = BatchProcess::pubkey(authority, ID, batch_id);
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::InitializeCrankProcess {
+            batch_process,
+            big_list: node(authority, &[]),
+            failures: BatchFailures::pubkey(batch_process),
+            vault: BatchProcess::vault(batch_process),
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeCrankProcess {
+            id: ID.to_string(),
+            batch_id,
+            batch_size,
+            amount,
+            bounty,
+            action: BatchAction::Transfer,
+            simulate,
+        }
+        .data(),
+    }
+}
+
+// One crank step paying `recipients`, the elements after `processed`
+pub fn crank_distribute_ix(
+    authority: Pubkey,
+    batch_id: u64,
+    processed: u32,
+    recipients: &[Pubkey],
+    keeper: Pubkey,
+) -> Instruction {
+    let batch_process = BatchProcess::pubkey(authority, ID, batch_id);
+    let (j, k) = (get_j(processed + 1), get_k(processed + 1));
+    let mut accounts = accounts::CrankDistribute {
+        batch_process,
+        big_list: node(authority, &[]),
+        big_list_j: node(authority, &[j]),
+        big_list_k: node(authority, &[j, k]),
+        receipts: DistributionReceipts::pubkey(batch_process, j, k),
+        failures: BatchFailures::pubkey(batch_process),
+        vault: BatchProcess::vault(batch_process),
+        keeper,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(
+        recipients
+            .iter()
+            .map(|recipient| AccountMeta::new(*recipient, false)),
+    );
+    Instruction {
+        program_id: big_list::ID,
+        accounts,
+        data: instruction::CrankDistribute {}.data(),
+    }
+}
+
+pub fn initialize_claim_ix(
+    authority: Pubkey,
+    batch_id: u64,
+    amount: u64,
+    expires_at: i64,
+) -> Instruction {
+    let batch_process = BatchProcess::pubkey(authority, ID, batch_id);
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::InitializeClaim {
+            batch_process,
+            big_list: node(authority, &[]),
+            vault: BatchProcess::vault(batch_process),
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializeClaim {
+            id: ID.to_string(),
+            batch_id,
+            amount,
+            mint: None,
+            expires_at,
+        }
+        .data(),
+    }
+}
+
+pub fn claim_ix(authority: Pubkey, batch_id: u64, index: u32, claimant: Pubkey) -> Instruction {
+    let batch_process = BatchProcess::pubkey(authority, ID, batch_id);
+    let (j, k) = (get_j(index + 1), get_k(index + 1));
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::Claim {
+            batch_process,
+            big_list: node(authority, &[]),
+            big_list_j: node(authority, &[j]),
+            big_list_k: node(authority, &[j, k]),
+            receipts: DistributionReceipts::pubkey(batch_process, j, k),
+            vault: BatchProcess::vault(batch_process),
+            claimant,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Claim { index }.data(),
+    }
+}
+
+pub fn reclaim_ix(authority: Pubkey, batch_id: u64) -> Instruction {
+    let batch_process = BatchProcess::pubkey(authority, ID, batch_id);
+    Instruction {
+        program_id: big_list::ID,
+        accounts: accounts::Reclaim {
+            batch_process,
+            vault: BatchProcess::vault(batch_process),
+            failures: BatchFailures::pubkey(batch_process),
+            authority,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Reclaim {}.data(),
+    }
+}
+
+// Moves the cluster clock, claims expire on its unix timestamp
+pub async fn set_unix_timestamp(context: &mut ProgramTestContext, unix_timestamp: i64) {
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    context.set_sysvar(&clock);
+}
+
+pub fn retry_failed_ix(
+    authority: Pubkey,
+    batch_id: u64,
+    j: u8,
+    k: u8,
+    recipients: &[Pubkey],
+) -> Instruction {
+    let batch_process = BatchProcess::pubkey(authority, ID, batch_id);
+    let mut accounts = accounts::RetryFailed {
+        batch_process,
+        big_list: node(authority, &[]),
+        big_list_k: node(authority, &[j, k]),
+        receipts: DistributionReceipts::pubkey(batch_process, j, k),
+        failures: BatchFailures::pubkey(batch_process),
+        vault: BatchProcess::vault(batch_process),
+        authority,
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    accounts.extend(
+        recipients
+            .iter()
+            .map(|recipient| AccountMeta::new(*recipient, false)),
+    );
+    Instruction {
+        program_id: big_list::ID,
+        accounts,
+        data: instruction::RetryFailed { j, k }.data(),
+    }
+}
+
+// Runs the thread's next instruction, returns false once it has none
+pub async fn exec_thread(context: &mut ProgramTestContext, thread: Pubkey) -> bool {
+    match exec_thread_ix(context, thread).await {
+        Some(ix) => {
+            send(context, &[ix]).await.unwrap();
+            true
+        }
+        None => false,
+    }
+}
+
+// What a worker would send to run the thread's next instruction
+pub async fn exec_thread_ix(
+    context: &mut ProgramTestContext,
+    thread: Pubkey,
+) -> Option<Instruction> {
+    let state: MockThread = {
+        let account = context
+            .banks_client
+            .get_account(thread)
+            .await
+            .unwrap()
+            .unwrap();
+        MockThread::deserialize(&mut account.data.as_slice()).unwrap()
+    };
+    let next = match state.next {
+        Some(next) => next,
+        None => return None,
+    };
+
+    let payer = context.payer.pubkey();
+    let mut metas = vec![
+        AccountMeta::new(thread, false),
+        AccountMeta::new(payer, true),
+    ];
+    for meta in &next.accounts {
+        let pubkey = if meta.pubkey == PAYER_PUBKEY {
+            payer
+        } else {
+            meta.pubkey
+        };
+        if pubkey == thread || pubkey == payer {
+            continue;
+        }
+        metas.push(if meta.is_writable {
+            AccountMeta::new(pubkey, false)
+        } else {
+            AccountMeta::new_readonly(pubkey, false)
+        });
+    }
+    metas.push(AccountMeta::new_readonly(next.program_id, false));
+
+    Some(Instruction {
+        program_id: clockwork_sdk::ID,
+        accounts: metas,
+        // Steps can repeat their accounts, the nonce keeps the transactions apart
+        data: EXEC_NONCE
+            .fetch_add(1, Ordering::Relaxed)
+            .to_le_bytes()
+            .to_vec(),
+    })
+}
@@ -0,0 +1,286 @@
+#![cfg(feature = "clockwork")]
+
+mod common;
+
+use anchor_lang::{
+    error::ErrorCode,
+    solana_program::instruction::{AccountMeta, InstructionError},
+};
+use big_list::state::{BigList, ChildSummary, IndexType, SetBucket};
+use common::*;
+use solana_program_test::BanksClientError;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, transaction::TransactionError};
+
+#[tokio::test]
+async fn it_initializes_a_list() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+
+    let root: BigList = fetch(&mut context, node(authority, &[])).await;
+    assert_eq!(root.total_elements, 0);
+    assert_eq!(root.elements, vec![node(authority, &[0])]);
+    let branch: BigList = fetch(&mut context, node(authority, &[0])).await;
+    assert_eq!(branch.elements, vec![node(authority, &[0, 0])]);
+
+    // The same id can not be initialized twice. Warping gives the retry a
+    // fresh blockhash so it isn't deduplicated.
+    context.warp_to_slot(100).unwrap();
+    assert!(send(&mut context, &[initialize_ix(authority)])
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn it_initializes_a_sorted_list() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(
+        &mut context,
+        &[initialize_list_ix(authority, false, IndexType::SortedIndex)],
+    )
+    .await
+    .unwrap();
+
+    for path in [&[][..], &[0], &[0, 0]] {
+        let list: BigList = fetch(&mut context, node(authority, path)).await;
+        assert_eq!(list.index_type, IndexType::SortedIndex);
+    }
+    let branch: BigList = fetch(&mut context, node(authority, &[0])).await;
+    assert_eq!(branch.summaries.len(), 1);
+}
+
+#[tokio::test]
+async fn it_appends_and_rolls_over_leaves() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+
+    // 275 elements roll over once, at the 11th append
+    let addresses = fill(&mut context, 275).await;
+
+    let root: BigList = fetch(&mut context, node(authority, &[])).await;
+    assert_eq!(root.total_elements, 275);
+    let branch: BigList = fetch(&mut context, node(authority, &[0])).await;
+    assert_eq!(
+        branch.elements,
+        vec![node(authority, &[0, 0]), node(authority, &[0, 1])]
+    );
+    assert_eq!(branch.total_elements, 275);
+    assert_eq!(branch.summaries[0].count, 256);
+    assert_eq!(branch.summaries[1].count, 19);
+
+    let first: BigList = fetch(&mut context, node(authority, &[0, 0])).await;
+    let second: BigList = fetch(&mut context, node(authority, &[0, 1])).await;
+    assert_eq!(first.elements, addresses[..256]);
+    assert_eq!(second.elements, addresses[256..]);
+}
+
+#[tokio::test]
+async fn it_rolls_over_from_a_full_leaf() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    // The first leaf is full, so the last append writes only to the next one
+    let first = fill(&mut context, 256).await;
+    let second = fill(&mut context, 5).await;
+
+    let branch: BigList = fetch(&mut context, node(authority, &[0])).await;
+    assert_eq!(branch.total_elements, 261);
+    assert_eq!(branch.summaries[0].count, 256);
+    assert_eq!(branch.summaries[1].count, 5);
+    let leaf: BigList = fetch(&mut context, node(authority, &[0, 0])).await;
+    assert_eq!(leaf.elements, first);
+    let leaf: BigList = fetch(&mut context, node(authority, &[0, 1])).await;
+    assert_eq!(leaf.elements, second);
+}
+
+#[tokio::test]
+async fn it_stops_at_the_end_of_the_first_branch() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(&mut context, &[initialize_ix(authority)])
+        .await
+        .unwrap();
+    // Filling a branch takes 65,536 elements, so the full branch and its
+    // last leaf are stored directly
+    let mut root: BigList = fetch(&mut context, node(authority, &[])).await;
+    let mut branch: BigList = fetch(&mut context, node(authority, &[0])).await;
+    let mut leaf: BigList = fetch(&mut context, node(authority, &[0, 0])).await;
+    leaf.elements = (0..256).map(|_| Pubkey::new_unique()).collect();
+    leaf.len = 256;
+    leaf.total_elements = 256;
+    let summary = ChildSummary::of_leaf(&leaf);
+    branch.elements = (0..=255).map(|k| node(authority, &[0, k])).collect();
+    branch.len = 256;
+    branch.total_elements = 256 * 256;
+    branch.summaries = vec![summary; 256];
+    root.total_elements = 256 * 256;
+    root.summaries[0] = ChildSummary::of_branch(&branch.summaries);
+    store(&mut context, node(authority, &[]), &root).await;
+    store(&mut context, node(authority, &[0]), &branch).await;
+    store(&mut context, node(authority, &[0, 255]), &leaf).await;
+
+    let ix = append_ix(authority, 256 * 256, vec![Pubkey::new_unique()]);
+    match send(&mut context, &[ix]).await {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(code),
+        ))) => assert_eq!(code, ErrorCode::ConstraintRaw as u32),
+        result => panic!("Expected a constraint error, got {:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn it_grows_a_bucket_past_the_heap() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    let prefix = 7;
+    send(
+        &mut context,
+        &[
+            initialize_list_ix(authority, true, IndexType::GrowableIndex),
+            initialize_bucket_ix(authority, prefix),
+        ],
+    )
+    .await
+    .unwrap();
+    let bucket = SetBucket::pubkey(node(authority, &[]), prefix);
+
+    // More keys in one bucket than a deserialized one could hold
+    let addresses: Vec<Pubkey> = (0..1100)
+        .map(|_| {
+            let mut bytes = Pubkey::new_unique().to_bytes();
+            bytes[0] = prefix;
+            Pubkey::new_from_array(bytes)
+        })
+        .collect();
+    for (i, chunk) in addresses.chunks(25).enumerate() {
+        let mut ix = append_ix(authority, i as u32 * 25, chunk.to_vec());
+        ix.accounts.push(AccountMeta::new(bucket, false));
+        send(&mut context, &[ix]).await.unwrap();
+    }
+
+    let state: SetBucket = fetch(&mut context, bucket).await;
+    let mut sorted = addresses.clone();
+    sorted.sort();
+    assert_eq!(state.keys, sorted);
+
+    let mut ix = append_ix(authority, 1100, vec![addresses[500]]);
+    ix.accounts.push(AccountMeta::new(bucket, false));
+    assert!(send(&mut context, &[ix]).await.is_err());
+}
+
+#[tokio::test]
+async fn it_only_overwrites_when_asked() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(
+        &mut context,
+        &[
+            initialize_with_capacity_ix(authority, 256),
+            allocate_leaves_ix(authority, 1),
+        ],
+    )
+    .await
+    .unwrap();
+    let addresses: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    send(
+        &mut context,
+        &[write_range_ix(authority, 0, addresses.clone(), false)],
+    )
+    .await
+    .unwrap();
+
+    let replacement = Pubkey::new_unique();
+    assert!(send(
+        &mut context,
+        &[write_range_ix(authority, 1, vec![replacement], false)]
+    )
+    .await
+    .is_err());
+    send(
+        &mut context,
+        &[write_range_ix(authority, 1, vec![replacement], true)],
+    )
+    .await
+    .unwrap();
+
+    let leaf: BigList = fetch(&mut context, node(authority, &[0, 0])).await;
+    assert_eq!(leaf.len, 3);
+    assert_eq!(
+        leaf.elements[..3],
+        [addresses[0], replacement, addresses[2]]
+    );
+}
+
+#[tokio::test]
+async fn it_only_syncs_full_leaves_before_the_last() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(
+        &mut context,
+        &[
+            initialize_with_capacity_ix(authority, 512),
+            allocate_leaves_ix(authority, 2),
+        ],
+    )
+    .await
+    .unwrap();
+    let addresses: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    send(
+        &mut context,
+        &[
+            write_range_ix(authority, 0, addresses.clone(), false),
+            write_range_ix(authority, 256, addresses, false),
+        ],
+    )
+    .await
+    .unwrap();
+
+    assert!(send(&mut context, &[sync_totals_ix(authority, 2)])
+        .await
+        .is_err());
+    send(&mut context, &[sync_totals_ix(authority, 1)])
+        .await
+        .unwrap();
+
+    let root: BigList = fetch(&mut context, node(authority, &[])).await;
+    assert_eq!(root.total_elements, 3);
+}
+
+#[tokio::test]
+async fn it_rejects_appends_to_prepaid_lists() {
+    let mut context = start().await;
+    let authority = context.payer.pubkey();
+    send(
+        &mut context,
+        &[
+            initialize_with_capacity_ix(authority, 256),
+            allocate_leaves_ix(authority, 1),
+        ],
+    )
+    .await
+    .unwrap();
+
+    // Prepaid leaves are written with `write_range`
+    let addresses: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let err = send(&mut context, &[append_ix(authority, 0, addresses)])
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(code),
+        )) if code == ErrorCode::ConstraintRaw as u32
+    ));
+    let root: BigList = fetch(&mut context, node(authority, &[])).await;
+    assert_eq!(root.total_elements, 0);
+}
@@ -5,10 +5,12 @@ import {
   appendATonOfAddresses,
   CLOCKWORK_THREAD_PROGRAM_ID,
   deriveAccountsForCurrentAndNextSize,
+  getBatchFailuresPDA,
   getBatchProccessPDA,
   getBigList,
   getClockworkThreadPDA,
   getCurrentIndices,
+  getVaultPDA,
 } from "../js";
 import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
 import { assert } from "chai";
@@ -60,7 +62,7 @@ describe("big-list", () => {
     );
 
     const tx = await program.methods
-      .initialize("my_big_list", 3)
+      .initialize("my_big_list", 3, false, { growableIndex: {} })
       .accounts({
         bigList,
         bigListJ,
@@ -155,24 +157,37 @@ describe("big-list", () => {
     const bigListJ = getBigList(program.provider.publicKey, listId, j);
     const bigListK = getBigList(program.provider.publicKey, listId, j, k);
 
+    const batchId = new anchor.BN(0);
     const batchProcess = await getBatchProccessPDA(
       program.provider.publicKey,
-      listId
+      listId,
+      batchId
     );
 
     const batchProcessorThread = await getClockworkThreadPDA(
       batchProcess,
-      listId
+      batchId.toString()
     );
     try {
           await program.methods
-      .initializeBatchProcess(listId)
+      .initializeBatchProcess(
+        listId,
+        batchId,
+        12,
+        { cron: { schedule: "*/30 * * * * * *" } },
+        new anchor.BN(100_000),
+        { transfer: {} },
+        0,
+        false
+      )
       .accounts({
         batchProcess,
         batchProcessorThread,
         bigList,
         bigListJ,
         bigListK,
+        failures: getBatchFailuresPDA(batchProcess),
+        vault: getVaultPDA(batchProcess),
         threadProgram: CLOCKWORK_THREAD_PROGRAM_ID,
       })
       .rpc();